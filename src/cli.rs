@@ -1,11 +1,15 @@
 use std::io::IsTerminal;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser, error::ErrorKind};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 
 use tali::{
-    output::{Config, Metric},
-    scanner::{Scanner, SortOrder},
+    output::{
+        Chart, ColorSupport, Column, Config, Format, GraphScale, HeatScale, Human, LineTerminator,
+        Metric, Scale, TableStyle, Template, Theme, ThemeSettings, is_valid_metric_name,
+        locale_separator,
+    },
+    scanner::{Policy, Scanner, SortKey, SortOrder},
 };
 
 /// A structural representation of the command-line arguments
@@ -37,6 +41,22 @@ pub struct Args {
     #[clap(short, long)]
     pub bytes: bool,
 
+    /// Choose and order the columns to show (e.g. `path,lines,graph,bytes`), overriding the toggles above
+    #[clap(long, value_delimiter = ',', value_name = "COLUMN")]
+    pub columns: Vec<Column>,
+
+    /// Whether the structured formats serialize every field, as no columns were explicitly selected
+    #[clap(skip)]
+    pub all_fields: bool,
+
+    /// Show each row's share of the total for the given metrics (e.g. `lines,bytes`)
+    #[clap(long, value_delimiter = ',', value_name = "METRIC")]
+    pub percent: Vec<Metric>,
+
+    /// The number of decimal places shown in the percentage columns
+    #[clap(long, default_value_t = 1, value_name = "N")]
+    pub percent_precision: usize,
+
     /// Group the results by language
     #[clap(short, long, aliases=["compact", "overview"])]
     pub group: bool,
@@ -46,22 +66,36 @@ pub struct Args {
     pub no_files: bool,
 
     // ** === SORTING OPTIONS === **
-    /// Sort on category
+    /// Sort on one or more categories, in order of priority (e.g. `language,lines:desc,path`)
+    #[clap(long, value_delimiter = ',', value_name = "KEY[:ORDER]")]
+    pub sort_by: Vec<SortKey>,
+
+    /// The order in which to sort the keys that don't specify one
+    /// (defaults to ascending for language and path, and descending otherwise)
     #[clap(long)]
-    pub sort_by: Option<Metric>,
+    pub sort_order: Option<SortOrder>,
+
+    // ** === FILTER OPTIONS === **
+    /// Only show the first N rows, summarising the rest in an "Others" row
+    #[clap(long, value_name = "N")]
+    pub top: Option<usize>,
+
+    /// Only show the rows with at least N lines, summarising the rest in an "Others" row
+    #[clap(long, value_name = "N")]
+    pub min_lines: Option<usize>,
 
-    /// The order in which to sort
-    #[clap(long, default_value = "descending")]
-    pub sort_order: SortOrder,
+    /// Only show the rows with at least N bytes, summarising the rest in an "Others" row
+    #[clap(long, value_name = "N")]
+    pub min_bytes: Option<u64>,
 
     // ** === GRAPH OPTIONS === **
     /// Show visualization
     #[clap(short = 'v', long, aliases = ["vis", "visualize", "visualization"])]
     pub graph: bool,
 
-    /// The character to use for the graph
-    #[clap(long, default_value = "▬")]
-    pub graph_fill: String,
+    /// The character to use for the graph (`▬` by default, or `█` for smooth bars)
+    #[clap(long)]
+    pub graph_fill: Option<String>,
 
     /// The character to use for the empty spaces in the graph
     #[clap(long, default_value = " ")]
@@ -75,6 +109,20 @@ pub struct Args {
     #[clap(long)]
     pub graph_by: Option<Metric>,
 
+    /// Scale the graph against the largest value (max), the total (total),
+    /// or logarithmically against the largest value (log)
+    #[clap(long, default_value = "max")]
+    pub graph_scale: GraphScale,
+
+    /// Draw smooth bars with a sub-character resolution
+    #[clap(long)]
+    pub graph_smooth: bool,
+
+    /// Split the bars into segments: the bar of each language into its files, and the bar of the total row into
+    /// the shares of each language
+    #[clap(long)]
+    pub graph_stacked: bool,
+
     // ** === SCANNING OPTIONS === **
     /// The maximum depth to recurse when scanning
     #[clap(short = 'd', long)]
@@ -91,7 +139,47 @@ pub struct Args {
     #[clap(long)]
     pub exclude: Option<String>,
 
+    // ** === CHECK OPTIONS === **
+    /// List the files and line numbers of lines longer than the given number of characters
+    #[clap(long, value_name = "N")]
+    pub max_line_length: Option<usize>,
+
+    /// List the files with more than the given number of lines
+    #[clap(long, value_name = "N")]
+    pub max_file_lines: Option<usize>,
+
+    /// Exit with an error (code 3) when the rule holds, listing the offending files: against each file
+    /// (e.g. `lines>1000`), the totals (e.g. `total.lines>500000`) or the number of files (e.g. `count>0`),
+    /// optionally only for a language (e.g. `language=JavaScript count>0`). Can be repeated
+    #[clap(long, value_name = "RULE")]
+    pub fail_if: Vec<Policy>,
+
+    // ** === HISTOGRAM OPTIONS === **
+    /// Show the distribution of the files over the given metric (e.g. lines or bytes) as a histogram
+    #[clap(long, value_name = "METRIC")]
+    pub histogram: Option<Metric>,
+
+    /// The number of buckets in a linear histogram
+    #[clap(long, default_value_t = 10)]
+    pub bins: usize,
+
+    /// How the histogram buckets are spaced (linear or log)
+    #[clap(long, default_value = "linear")]
+    pub histogram_scale: Scale,
+
+    /// Split each histogram bar into coloured per-language segments
+    #[clap(long)]
+    pub stacked: bool,
+
     // ** === TABLE OPTIONS === **
+    /// The style to draw the table in (plain, compact, ascii-grid, rounded, heavy or markdown)
+    #[clap(long, default_value = "plain")]
+    pub table_style: TableStyle,
+
+    /// The width the table has to fit in (defaults to the width of the terminal)
+    #[clap(long, value_name = "N")]
+    pub width: Option<usize>,
+
     /// Disable the header row
     #[clap(long, default_value_t = false)]
     pub no_header: bool,
@@ -100,18 +188,126 @@ pub struct Args {
     #[clap(long, default_value_t = false)]
     pub no_footer: bool,
 
+    /// Show distribution statistics (min, mean, median, p90, p99, std dev) as extra footer rows
+    #[clap(long, default_value_t = false)]
+    pub stats: bool,
+
     /// Disable the table columns
     #[clap(long, default_value_t = false)]
     pub no_align: bool,
 
+    /// Show byte sizes in units (KiB, MiB, ...) and counts with thousands separators.
+    /// Only applies to the tables, unless `all` is given
+    #[clap(long, num_args = 0..=1, default_missing_value = "table", value_name = "table|all")]
+    pub human: Option<Human>,
+
+    /// Use SI units (kB, MB, ...) instead of binary units for the human-readable byte sizes
+    #[clap(long)]
+    pub si: bool,
+
+    // ** === DELIMITED OUTPUT OPTIONS === **
+    /// The separator of the fields in the CSV and TSV output (e.g. `;` or `\t`). Implies `--format csv`
+    #[clap(long, value_name = "SEP", value_parser = parse_delimiter)]
+    pub delimiter: Option<String>,
+
+    /// The line terminator of the CSV and TSV output (lf or crlf)
+    #[clap(long, default_value = "lf", value_name = "lf|crlf")]
+    pub line_terminator: LineTerminator,
+
+    /// Leave the total and the statistics rows out of the CSV and TSV data, and print them to stderr instead
+    #[clap(long)]
+    pub no_footer_in_data: bool,
+
+    /// Print the row of each file as soon as it is scanned (csv, tsv and ndjson only). The rows are in
+    /// the order the files are scanned, and the percentage and graph columns are left out
+    #[clap(long, conflicts_with_all = ["group", "sort_by", "top", "min_lines", "min_bytes", "histogram", "max_line_length", "max_file_lines", "template", "template_file"])]
+    pub stream: bool,
+
+    // ** === TEMPLATE OPTIONS === **
+    /// Render the output with a template instead of a format, e.g. `'{path}: {lines}\n'`.
+    /// The `{#header}`, `{#row}` and `{#footer}` markers start the sections of the template
+    #[clap(long, conflicts_with = "template_file")]
+    pub template: Option<String>,
+
+    /// Read the template from a file
+    #[clap(long, value_name = "FILE")]
+    pub template_file: Option<std::path::PathBuf>,
+
+    /// The template parsed from `--template` or `--template-file`
+    #[clap(skip)]
+    pub parsed_template: Option<Template>,
+
     // ** === OUTPUT OPTIONS=== **
-    /// Disable ANSI colors
+    /// When to use ANSI colors (auto, always or never). Auto colors the output when it is a terminal, with the
+    /// colors the terminal supports.
+    #[clap(long, default_value = "auto", value_name = "WHEN")]
+    pub color: ColorMode,
+
+    /// Disable ANSI colors (same as `--color never`)
     #[clap(long, alias="plain", default_value_t = std::env::var("NO_COLOR").is_ok_and(|v| v.to_lowercase() == "true"))]
     pub no_color: bool,
 
+    /// Color the numeric cells on a gradient relative to the largest value in the column (max)
+    /// or to their rank among the rows (percentile)
+    #[clap(long, num_args = 0..=1, default_missing_value = "max", value_name = "max|percentile")]
+    pub heatmap: Option<HeatScale>,
+
+    /// Draw the shares of each language in the graph metric as an SVG chart (bar, pie or donut),
+    /// or its total as a badge (badge). Implies `--format svg`
+    #[clap(long, value_name = "bar|pie|donut|badge")]
+    pub chart: Option<Chart>,
+
+    /// The label of the SVG badge (defaults to the name of the graph metric)
+    #[clap(long, value_name = "TEXT")]
+    pub badge_label: Option<String>,
+
+    /// The prefix of the metric names in the OpenMetrics output
+    #[clap(long, default_value = "tali", value_name = "PREFIX", value_parser = parse_metric_prefix)]
+    pub metric_prefix: String,
+
+    /// A label added to every sample of the OpenMetrics output, e.g. `--metric-label repo=tali`
+    /// (can be repeated)
+    #[clap(long, value_delimiter = ',', value_name = "NAME=VALUE", value_parser = parse_metric_label)]
+    pub metric_label: Vec<(String, String)>,
+
+    /// The color theme (default, vivid, mono, or one defined in the configuration file)
+    #[clap(long)]
+    pub theme: Option<String>,
+
+    /// The configuration file to read the themes from
+    /// (defaults to `$TALI_CONFIG`, or `tali/config.toml` in the user's config directory)
+    #[clap(long, value_name = "FILE")]
+    pub config: Option<std::path::PathBuf>,
+
+    /// The theme resolved from `--theme` and the configuration file
+    #[clap(skip)]
+    pub resolved_theme: Theme,
+
     /// The output format
     #[clap(short, long, default_value = "table")]
-    pub format: tali::output::Format,
+    pub format: Format,
+}
+
+/// When to color the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// When the output is a terminal, as supported by it (detected from `COLORTERM` and `TERM`)
+    Auto,
+    /// Even when the output is not a terminal, or the terminal doesn't seem to support them
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "always" | "on" => Ok(Self::Always),
+            "never" | "off" => Ok(Self::Never),
+            x => Err(format!("Invalid color mode: {x}")),
+        }
+    }
 }
 
 impl Args {
@@ -129,7 +325,7 @@ impl Args {
         }
 
         // If all the flags are false, then do nothing and just use the defaults
-        let show_all = vec![
+        let show_all = [
             self.language,
             self.lines,
             self.words,
@@ -138,7 +334,7 @@ impl Args {
             self.graph,
         ]
         .iter()
-        .all(|toggle| *toggle == false);
+        .all(|toggle| !*toggle);
 
         if show_all {
             self.language = true;
@@ -149,56 +345,245 @@ impl Args {
             self.graph = true;
         }
 
-        // Determine the default sort
-        if self.sort_by.is_none() {
-            if self.lines {
-                self.sort_by = Some(Metric::Lines)
-            } else if self.words {
-                self.sort_by = Some(Metric::Words)
-            } else if self.chars {
-                self.sort_by = Some(Metric::Chars)
-            } else {
-                self.sort_by = Some(Metric::Bytes)
-            }
-        }
-
         // Enable `--graph` if `--graph-by` was provided
         if !self.graph && self.graph_by.is_some() {
             self.graph = true;
         }
 
+        // Draw the chart in SVG if `--chart` was provided (instead of the default table)
+        if self.chart.is_some() && matches!(self.format, Format::Table) {
+            self.format = Format::SVG;
+        }
+
+        // Parse the template (the inline one may contain escape sequences, like `\n`)
+        let template = match (&self.template, &self.template_file) {
+            (Some(template), _) => Some(Template::unescape(template)),
+            (None, Some(path)) => Some(std::fs::read_to_string(path).unwrap_or_else(|e| {
+                let message = format!("Failed to read the template {}: {e}", path.display());
+                Self::command().error(ErrorKind::Io, message).exit()
+            })),
+            (None, None) => None,
+        };
+        self.parsed_template = template.map(|template| {
+            template
+                .parse()
+                .unwrap_or_else(|e| Self::command().error(ErrorKind::InvalidValue, e).exit())
+        });
+
+        // Output delimiter-separated values if `--delimiter` was provided (instead of the default table)
+        if self.delimiter.is_some() && matches!(self.format, Format::Table) {
+            self.format = Format::CSV;
+        }
+
+        // Unless the columns were explicitly specified, select them from the toggles in the default order
+        self.all_fields = self.columns.is_empty();
+        if self.columns.is_empty() {
+            self.columns = Column::DEFAULT
+                .into_iter()
+                .filter(|column| match column {
+                    Column::Language => self.language,
+                    Column::Path => true,
+                    Column::Lines => self.lines,
+                    Column::Words => self.words,
+                    Column::Chars => self.chars,
+                    Column::Bytes => self.bytes,
+                    Column::Graph => self.graph,
+                    _ => false,
+                })
+                .collect();
+        } else if self.graph_by.is_some() && !self.columns.contains(&Column::Graph) {
+            self.columns.push(Column::Graph);
+        }
+
+        // Place each percentage column right after the column of its metric (or before the graph, or at the end)
+        for metric in &self.percent {
+            if !metric.is_additive() {
+                Self::command()
+                    .error(
+                        ErrorKind::InvalidValue,
+                        format!("cannot show the percentage of the total for `{metric}`"),
+                    )
+                    .exit();
+            }
+            let column = Column::Percent(*metric);
+            if self.columns.contains(&column) {
+                continue;
+            }
+            let index = self
+                .columns
+                .iter()
+                .position(|c| *c == Column::from(*metric))
+                .map(|i| i + 1)
+                .or_else(|| self.columns.iter().position(|c| *c == Column::Graph))
+                .unwrap_or(self.columns.len());
+            self.columns.insert(index, column);
+        }
+
+        if self.no_files {
+            self.columns.retain(|column| *column != Column::Path);
+        }
+
+        // Determine the default sort: the longest lines in check mode, otherwise the first numeric column
+        if self.sort_by.is_empty() {
+            let metric = if self.max_line_length.is_some() {
+                Metric::MaxLineLength
+            } else if self.max_file_lines.is_some() {
+                Metric::Lines
+            } else {
+                self.columns
+                    .iter()
+                    .find_map(|column| column.metric())
+                    .unwrap_or(Metric::Bytes)
+            };
+            self.sort_by.push(metric.into());
+        }
+
+        // Resolve the theme from the configuration file
+        let settings = self.load_theme_settings();
+        self.resolved_theme = settings
+            .resolve(self.theme.as_deref())
+            .unwrap_or_else(|e| Self::command().error(ErrorKind::InvalidValue, e).exit());
+
+        // Only the formats with a row per file can be streamed
+        if self.stream && !matches!(self.format, Format::CSV | Format::TSV | Format::NDJSON) {
+            Self::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--stream requires the csv, tsv or ndjson format",
+                )
+                .exit();
+        }
+
+        // The histogram can only bucket numeric metrics
+        if self.histogram.is_some_and(|metric| !metric.is_numeric()) {
+            Self::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    "the histogram requires a numeric metric (e.g. lines or bytes)",
+                )
+                .exit();
+        }
+
         self
     }
+
+    /// Reads the theme settings from the configuration file. A missing default configuration file is not an error.
+    fn load_theme_settings(&self) -> ThemeSettings {
+        let Some(path) = self.config.clone().or_else(default_config_path) else {
+            return ThemeSettings::default();
+        };
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) if self.config.is_none() => return ThemeSettings::default(),
+            Err(e) => {
+                let message = format!("failed to read {}: {e}", path.display());
+                Self::command().error(ErrorKind::Io, message).exit()
+            }
+        };
+        ThemeSettings::from_toml(&contents).unwrap_or_else(|e| {
+            let message = format!("invalid configuration file {}: {e}", path.display());
+            Self::command()
+                .error(ErrorKind::InvalidValue, message)
+                .exit()
+        })
+    }
+
+    /// The colors the output should use, based on `--color` and the capabilities of the terminal
+    fn color_support(&self) -> ColorSupport {
+        let mode = if self.no_color {
+            ColorMode::Never
+        } else {
+            self.color
+        };
+        match mode {
+            ColorMode::Never => ColorSupport::None,
+            ColorMode::Auto if !std::io::stdout().is_terminal() => ColorSupport::None,
+            ColorMode::Auto => ColorSupport::detect(),
+            ColorMode::Always => ColorSupport::detect().max(ColorSupport::Ansi16),
+        }
+    }
+}
+
+/// The default location of the configuration file
+fn default_config_path() -> Option<std::path::PathBuf> {
+    if let Some(path) = std::env::var_os("TALI_CONFIG") {
+        return Some(path.into());
+    }
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| std::env::var_os("APPDATA"))
+        .map(std::path::PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".config"))
+        })?;
+    Some(config_dir.join("tali").join("config.toml"))
 }
 
 impl From<&Args> for Config {
     fn from(args: &Args) -> Self {
-        let sort_by = args.sort_by.unwrap_or(Metric::Bytes);
-        let graph_by = args.graph_by.unwrap_or(sort_by);
+        // Graph the first numeric sort key by default
+        let graph_by = args.graph_by.unwrap_or_else(|| {
+            args.sort_by
+                .iter()
+                .map(|key| key.metric)
+                .find(|metric| metric.is_numeric())
+                .unwrap_or(Metric::Bytes)
+        });
         Self {
-            files: !args.no_files,
-
-            language: args.language,
-            lines: args.lines,
-            words: args.words,
-            chars: args.chars,
-            bytes: args.bytes,
+            columns: args.columns.clone(),
+            all_fields: args.all_fields,
             group_by_language: args.group,
 
-            graph: args.graph,
             graph_by,
-            graph_fill: args.graph_fill.clone(),
+            graph_scale: args.graph_scale,
+            graph_fill: args
+                .graph_fill
+                .clone()
+                .unwrap_or_else(|| if args.graph_smooth { "█" } else { "▬" }.to_string()),
             graph_blank: args.graph_blank.clone(),
             graph_size: args.graph_size,
+            graph_smooth: args.graph_smooth,
+            graph_stacked: args.graph_stacked,
+
+            max_line_length: args.max_line_length,
+            max_file_lines: args.max_file_lines,
 
-            sort_by,
+            histogram: args.histogram,
+            histogram_scale: args.histogram_scale,
+            histogram_bins: args.bins,
+            histogram_stacked: args.stacked,
+
+            sort_by: args.sort_by.clone(),
             sort_order: args.sort_order,
 
+            percent_precision: args.percent_precision,
+
+            // `--si` implies human-readable sizes
+            human: args.human.or(args.si.then_some(Human::Table)),
+            si: args.si,
+            thousands_separator: locale_separator(),
+
+            top: args.top,
+            min_lines: args.min_lines,
+            min_bytes: args.min_bytes,
+
+            table_style: args.table_style,
+            width: args.width.or_else(terminal_width),
             header: !args.no_header,
             footer: !args.no_footer,
+            stats: args.stats,
             alignment: !args.no_align,
-
-            use_colors: !args.no_color,
+            delimiter: args.delimiter.clone(),
+            line_terminator: args.line_terminator,
+            footer_in_data: !args.no_footer_in_data,
+
+            color_support: args.color_support(),
+            theme: args.resolved_theme.clone(),
+            heatmap: args.heatmap,
+            template: args.parsed_template.clone(),
+            chart: args.chart.unwrap_or_default(),
+            badge_label: args.badge_label.clone(),
+            metric_prefix: args.metric_prefix.clone(),
+            metric_labels: args.metric_label.clone(),
             format: args.format,
         }
     }
@@ -211,7 +596,8 @@ impl From<&Args> for Scanner {
         scanner
             .ignore_hidden(!args.hidden)
             .max_filesize(args.max_filesize)
-            .scan_depth(args.max_depth);
+            .scan_depth(args.max_depth)
+            .max_line_length(args.max_line_length);
 
         if let Some(patterns) = &args.exclude {
             let exclude = build_glob_set(patterns);
@@ -222,7 +608,49 @@ impl From<&Args> for Scanner {
     }
 }
 
-fn build_glob_set(patterns: &String) -> GlobSet {
+/// Parses the `--delimiter`, translating the escaped tab (`\t`) that can't be typed easily in a shell
+fn parse_delimiter(delimiter: &str) -> Result<String, String> {
+    match delimiter {
+        "" => Err("The delimiter can't be empty".to_string()),
+        "\\t" | "tab" => Ok("\t".to_string()),
+        delimiter if delimiter.contains(['"', '\n', '\r']) => {
+            Err("The delimiter can't contain quotes or line breaks".to_string())
+        }
+        delimiter => Ok(delimiter.to_string()),
+    }
+}
+
+fn parse_metric_prefix(prefix: &str) -> Result<String, String> {
+    if is_valid_metric_name(prefix) {
+        Ok(prefix.to_string())
+    } else {
+        Err("The prefix can only contain letters, digits and underscores, and can't start with a digit".to_string())
+    }
+}
+
+fn parse_metric_label(label: &str) -> Result<(String, String), String> {
+    let (name, value) = label
+        .split_once('=')
+        .ok_or_else(|| format!("Expected NAME=VALUE, got {label}"))?;
+    if !is_valid_metric_name(name) || name.starts_with("__") {
+        return Err(format!("Invalid label name: {name}"));
+    }
+    if matches!(name, "language" | "le") {
+        return Err(format!("The {name} label is reserved"));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// The width of the terminal, if the output is going to one
+fn terminal_width() -> Option<usize> {
+    let stdout = std::io::stdout();
+    if !stdout.is_terminal() {
+        return None;
+    }
+    terminal_size::terminal_size_of(&stdout).map(|(terminal_size::Width(width), _)| width as usize)
+}
+
+fn build_glob_set(patterns: &str) -> GlobSet {
     let mut builder = GlobSetBuilder::new();
     for pattern in patterns.split(",") {
         if let Ok(glob) = Glob::new(pattern.trim()) {
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+/// The errors that can occur while scanning
+#[derive(Debug)]
+pub enum Error {
+    /// A path could not be read (e.g. it doesn't exist, or it is not valid UTF-8)
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A directory could not be walked (e.g. a permission error, or a malformed `.gitignore`)
+    Walk(ignore::Error),
+}
+
+/// A [`Result`][std::result::Result] with the error type of the crate
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Error {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Error::Walk(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Walk(error) => Some(error),
+        }
+    }
+}
+
+impl From<ignore::Error> for Error {
+    fn from(error: ignore::Error) -> Self {
+        Error::Walk(error)
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io { source, .. } => source,
+            Error::Walk(error) => std::io::Error::other(error),
+        }
+    }
+}
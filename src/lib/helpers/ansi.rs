@@ -1,19 +1,23 @@
+use unicode_width::UnicodeWidthStr;
+
 /// Character that denotes the starts of escape codes
 const ESC: char = '\x1b';
 
-/// Determine the visible width (i.e. the number of printable characters) of an ANSI string.
+/// Determine the visible width (i.e. the number of terminal columns occupied) of an ANSI string.
 ///
+/// The printable text is measured by its East-Asian display width, so wide characters (like CJK ideographs
+/// and most emoji) count as two columns while zero-width characters (like combining marks) count as none.
 /// This function skips ANSI escape sequences (CSI sequences starting with [`ESC`] followed by `[`)
 /// so that ansi color codes do not affect width calculations. Note that it is designed for CSI-style
 /// escape sequences and may not correctly handle other types (yet).
 pub fn visible_width(s: &str) -> usize {
-    let mut width: usize = 0;
+    let mut visible = String::with_capacity(s.len());
     let mut chars = s.chars();
 
     while let Some(c) = chars.next() {
         // If we have not encountered a ESC yet ...
         if c != ESC {
-            width += 1
+            visible.push(c)
         } else {
             // .. otherwise, we hit the start of an ESC sequence
             'esc_sequence: while let Some(c) = chars.next() {
@@ -28,13 +32,22 @@ pub fn visible_width(s: &str) -> usize {
                             }
                         }
                     }
-                    _ => width += 1,
+                    _ => visible.push(c),
                 }
             }
         }
     }
 
-    width
+    visible.width()
+}
+
+/// Repeats the pattern as many times as fits in the given number of terminal columns (measured by its
+/// [visible width][visible_width]), and pads the rest with spaces so that the result is exactly `width` columns wide.
+/// A pattern without any width (e.g. an empty string) is replaced by spaces entirely.
+pub fn repeat_to_width(pattern: &str, width: usize) -> String {
+    let pattern_width = visible_width(pattern);
+    let count = width.checked_div(pattern_width).unwrap_or_default();
+    pattern.repeat(count) + &" ".repeat(width - count * pattern_width)
 }
 
 #[cfg(test)]
@@ -47,6 +60,16 @@ mod tests {
         assert_eq!(str.len(), visible_width(str))
     }
 
+    #[test]
+    fn should_repeat_patterns_to_the_given_width() {
+        assert_eq!(repeat_to_width("#", 3), "###");
+        assert_eq!(repeat_to_width("=>", 5), "=>=> ");
+        assert_eq!(repeat_to_width("█", 2), "██");
+        assert_eq!(repeat_to_width("日", 3), "日 ");
+        assert_eq!(repeat_to_width("", 2), "  ");
+        assert_eq!(repeat_to_width("\x1b[31m\x1b[0m", 1), " ");
+    }
+
     #[test]
     fn should_return_zero_for_empty_string() {
         assert_eq!(0, visible_width(""))
@@ -65,4 +88,15 @@ mod tests {
         let ansi_str = "\x1b[31mHello \x1b[32mWorld!\x1b[0m";
         assert_eq!(str.len(), visible_width(ansi_str))
     }
+
+    #[test]
+    fn should_count_wide_characters_as_two_columns() {
+        assert_eq!(9, visible_width("日本語.rs"));
+        assert_eq!(9, visible_width("\x1b[31m日本語\x1b[0m.rs"));
+    }
+
+    #[test]
+    fn should_not_count_combining_marks() {
+        assert_eq!(4, visible_width("cafe\u{0301}"));
+    }
 }
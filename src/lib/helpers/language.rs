@@ -9,6 +9,7 @@ macro_rules! define_languages {
         $(with RGB($colorR:expr, $colorG:expr, $colorB:expr))?      // Matches: with RGB(255, 165, 0)   | RGB color for the language
     ),* $(,)? ) => {                                                // Matches: ,                       | Optional trailing comma
         #[derive(Debug, Serialize, Clone, PartialEq, Eq, Hash)]
+        #[allow(clippy::upper_case_acronyms)]
         pub enum Language {
             $($language),*,
             Unknown(String),
@@ -42,6 +43,13 @@ macro_rules! define_languages {
             }
         }
 
+        /// Files without an extension are assumed to be [text][Language::Text]
+        impl Default for Language {
+            fn default() -> Self {
+                Language::Text
+            }
+        }
+
         impl std::fmt::Display for Language {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 match self {
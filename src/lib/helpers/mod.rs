@@ -1,4 +1,5 @@
 pub(crate) mod ansi;
 pub mod language;
+pub mod number;
 pub mod path;
 pub mod table;
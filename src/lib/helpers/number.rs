@@ -0,0 +1,154 @@
+/// Formats a byte count with the largest fitting unit, e.g. `1.5 KiB` (binary units) or `1.5 kB` (SI units).
+/// The decimal point becomes a comma when the thousands separator is a period (like in [`group_decimal`]).
+pub fn format_bytes(bytes: u64, si: bool, separator: char) -> String {
+    let (base, units) = if si {
+        (1000.0, ["B", "kB", "MB", "GB", "TB", "PB", "EB"])
+    } else {
+        (1024.0, ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"])
+    };
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        let value = group_decimal(&format!("{value:.1}"), separator);
+        format!("{} {}", value, units[unit])
+    }
+}
+
+/// Abbreviates a count with a metric suffix, e.g. `1234` becomes `1.2k` and `5600000` becomes `5.6M`
+pub fn abbreviate(value: u64) -> String {
+    let units = ["", "k", "M", "G", "T", "P", "E"];
+    let mut scaled = value as f64;
+    let mut unit = 0;
+    while scaled >= 999.95 && unit < units.len() - 1 {
+        scaled /= 1000.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        value.to_string()
+    } else {
+        format!("{:.1}{}", scaled, units[unit])
+    }
+}
+
+/// Inserts the separator between each group of three digits, e.g. `1234567` becomes `1,234,567`
+pub fn group_digits(value: u64, separator: char) -> String {
+    let digits = value.to_string();
+    let mut res = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            res.push(separator);
+        }
+        res.push(digit);
+    }
+    res
+}
+
+/// Inserts the separator between the groups of thousands of a decimal number's integer part, e.g. `1234.5`
+/// becomes `1,234.5`. The decimal point becomes a comma when the separator is a period (e.g. `1.234,5`).
+pub fn group_decimal(value: &str, separator: char) -> String {
+    let (integer, fraction) = match value.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (value, None),
+    };
+    let Ok(integer) = integer.parse::<u64>() else {
+        return value.to_string();
+    };
+    let point = if separator == '.' { ',' } else { '.' };
+    match fraction {
+        Some(fraction) => format!("{}{point}{fraction}", group_digits(integer, separator)),
+        None => group_digits(integer, separator),
+    }
+}
+
+/// Determines the thousands separator from the locale in the environment
+/// (`LC_ALL`, then `LC_NUMERIC`, then `LANG`), defaulting to a comma
+pub fn locale_separator() -> char {
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    separator_for(&locale)
+}
+
+/// The thousands separator conventionally used by the given locale (e.g. `de_DE.UTF-8`)
+fn separator_for(locale: &str) -> char {
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    let (language, region) = locale.split_once(['_', '-']).unwrap_or((locale, ""));
+    match (language, region) {
+        ("de" | "it", "CH") => '\'',
+        (
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl"
+            | "sr",
+            _,
+        ) => '.',
+        (
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "uk" | "hu"
+            | "bg" | "et" | "lt" | "lv",
+            _,
+        ) => ' ',
+        _ => ',',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0, false, ','), "0 B");
+        assert_eq!(format_bytes(1023, false, ','), "1023 B");
+        assert_eq!(format_bytes(1536, false, ','), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024, false, ','), "5.0 MiB");
+        assert_eq!(format_bytes(1500, true, ','), "1.5 kB");
+        assert_eq!(format_bytes(u64::MAX, true, ','), "18.4 EB");
+        assert_eq!(format_bytes(1536, false, '.'), "1,5 KiB");
+        assert_eq!(format_bytes(1023, false, '.'), "1023 B");
+    }
+
+    #[test]
+    fn test_abbreviate() {
+        assert_eq!(abbreviate(0), "0");
+        assert_eq!(abbreviate(999), "999");
+        assert_eq!(abbreviate(1234), "1.2k");
+        assert_eq!(abbreviate(999_960), "1.0M");
+        assert_eq!(abbreviate(5_600_000), "5.6M");
+        assert_eq!(abbreviate(u64::MAX), "18.4E");
+    }
+
+    #[test]
+    fn test_group_digits() {
+        assert_eq!(group_digits(0, ','), "0");
+        assert_eq!(group_digits(999, ','), "999");
+        assert_eq!(group_digits(1000, ','), "1,000");
+        assert_eq!(group_digits(1234567, '.'), "1.234.567");
+    }
+
+    #[test]
+    fn test_group_decimal() {
+        assert_eq!(group_decimal("12.5", ','), "12.5");
+        assert_eq!(group_decimal("1234.56", ','), "1,234.56");
+        assert_eq!(group_decimal("1234.56", '.'), "1.234,56");
+        assert_eq!(group_decimal("1234567", ' '), "1 234 567");
+    }
+
+    #[test]
+    fn test_separator_for_locale() {
+        assert_eq!(separator_for("en_US.UTF-8"), ',');
+        assert_eq!(separator_for("de_DE.UTF-8"), '.');
+        assert_eq!(separator_for("de_CH"), '\'');
+        assert_eq!(separator_for("fr_FR@euro"), ' ');
+        assert_eq!(separator_for("C"), ',');
+        assert_eq!(separator_for(""), ',');
+    }
+}
@@ -1,6 +1,135 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
 /// Display format for the path
 pub fn display(path: &std::path::Path) -> String {
     let path = path.display().to_string();
     let path = path.trim_start_matches(".\\").trim_start_matches("./");
     path.to_string()
 }
+
+/// Shortens the path to fit within the given display width by replacing its middle with an ellipsis.
+///
+/// Whole directories are elided where possible, keeping the first component and as many of the trailing
+/// ones as fit (e.g. `src/…/deep/file.rs`). Otherwise, the middle of the text itself is elided.
+pub fn truncate_middle(path: &str, width: usize) -> String {
+    if path.width() <= width {
+        return path.to_string();
+    } else if width == 0 {
+        return String::new();
+    }
+
+    let parts = path.split(['/', '\\']).collect::<Vec<_>>();
+    if parts.len() > 2 {
+        let separator = if path.contains('/') { "/" } else { "\\" };
+        for keep in (1..parts.len() - 1).rev() {
+            let tail = parts[parts.len() - keep..].join(separator);
+            let candidate = format!("{}{separator}…{separator}{}", parts[0], tail);
+            if candidate.width() <= width {
+                return candidate;
+            }
+        }
+    }
+
+    // Keep as much of the start and end as fits on either side of the ellipsis
+    let available = width.saturating_sub(1);
+    let (mut head, mut tail) = (String::new(), Vec::new());
+    let (mut head_width, mut tail_width) = (0, 0);
+    for c in path.chars() {
+        let w = c.width().unwrap_or_default();
+        if head_width + w > available / 2 {
+            break;
+        }
+        head.push(c);
+        head_width += w;
+    }
+    for c in path.chars().rev() {
+        let w = c.width().unwrap_or_default();
+        if tail_width + w > available - head_width {
+            break;
+        }
+        tail.push(c);
+        tail_width += w;
+    }
+    head + "…" + &tail.into_iter().rev().collect::<String>()
+}
+
+/// Compares two strings in natural (alphanumeric) order, so that `file2` sorts before `file10`.
+///
+/// Runs of ASCII digits are compared by their numeric value and everything else is compared case-insensitively.
+/// Strings that are otherwise equal (e.g. `a01` and `a1`, or `A` and `a`) fall back to a plain comparison.
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let (mut x, mut y) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let ordering = match (x.peek(), y.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(c), Some(d)) if c.is_ascii_digit() && d.is_ascii_digit() => {
+                let (m, n) = (digits(&mut x), digits(&mut y));
+                let (m, n) = (m.trim_start_matches('0'), n.trim_start_matches('0'));
+                m.len().cmp(&n.len()).then_with(|| m.cmp(n))
+            }
+            (Some(c), Some(d)) => {
+                let ordering = c.to_lowercase().cmp(d.to_lowercase());
+                x.next();
+                y.next();
+                ordering
+            }
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+/// Consumes the run of ASCII digits at the front of the iterator
+fn digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn test_truncate_middle() {
+        let path = "src/lib/output/deep/file.rs";
+        assert_eq!(truncate_middle(path, 40), path);
+        assert_eq!(truncate_middle(path, 18), "src/…/deep/file.rs");
+        assert_eq!(truncate_middle(path, 13), "src/…/file.rs");
+        assert_eq!(truncate_middle(path, 9), "src/…e.rs");
+        assert_eq!(
+            truncate_middle("a_very_long_file_name.rs", 10),
+            "a_ve…me.rs"
+        );
+        assert_eq!(truncate_middle("日本語のファイル.rs", 8), "日…ル.rs");
+        assert_eq!(truncate_middle(path, 0), "");
+    }
+
+    #[test]
+    fn test_natural_cmp_numbers() {
+        assert_eq!(natural_cmp("file2.rs", "file10.rs"), Ordering::Less);
+        assert_eq!(natural_cmp("file10.rs", "file9.rs"), Ordering::Greater);
+        assert_eq!(natural_cmp("v1.10", "v1.9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_natural_cmp_case_insensitive() {
+        assert_eq!(natural_cmp("apple", "Banana"), Ordering::Less);
+        assert_eq!(natural_cmp("src/Main.rs", "src/lib.rs"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_natural_cmp_ties() {
+        assert_eq!(natural_cmp("a", "a"), Ordering::Equal);
+        assert_eq!(natural_cmp("a1", "a01"), "a1".cmp("a01"));
+        assert_eq!(natural_cmp("a", "ab"), Ordering::Less);
+    }
+}
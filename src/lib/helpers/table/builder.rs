@@ -88,7 +88,7 @@ impl TableBuilder {
         };
         // Builds the footer
         let footer = if self.has_footer {
-            rows.pop().unwrap_or(Vec::new())
+            rows.pop().into_iter().collect()
         } else {
             Vec::new()
         };
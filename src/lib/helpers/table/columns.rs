@@ -28,7 +28,8 @@ impl Columns {
     /// Calculates column widths based on the provided rows.
     ///
     /// Each row is expected to be an iterable of strings, representing table cells.
-    /// This function ensures that `widths` are updated to the longest/widest value per column
+    /// This function ensures that `widths` are updated to the widest value per column, as measured by
+    /// the number of terminal columns each cell occupies (see [`ansi::visible_width`])
     pub fn calculate<I>(&mut self, rows: I)
     where
         I: IntoIterator,
@@ -50,7 +51,7 @@ impl Columns {
             }
 
             for (i, cell) in row.iter().enumerate() {
-                self.widths[i] = self.widths[i].max(ansi::visible_width(&cell));
+                self.widths[i] = self.widths[i].max(ansi::visible_width(cell));
             }
         }
 
@@ -75,9 +76,9 @@ mod tests {
     #[test]
     fn test_mark_for_recalc() {
         let mut cols = Columns::default();
-        assert_eq!(cols.needs_recalculation, false);
+        assert!(!cols.needs_recalculation);
         cols.mark_for_recalc();
-        assert_eq!(cols.needs_recalculation, true);
+        assert!(cols.needs_recalculation);
     }
 
     #[test]
@@ -115,6 +116,20 @@ mod tests {
         assert_eq!(cols.widths, vec![10, 5]);
     }
 
+    #[test]
+    fn test_calculate_uses_display_width() {
+        let mut cols = Columns::default();
+        cols.mark_for_recalc();
+
+        let rows = vec![
+            vec!["日本語.rs".to_string(), "1".to_string()],
+            vec!["main.rs".to_string(), "2".to_string()],
+        ];
+
+        cols.calculate(rows);
+        assert_eq!(cols.widths, vec![9, 1]);
+    }
+
     #[test]
     fn test_iteration() {
         let cols = Columns {
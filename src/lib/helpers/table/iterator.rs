@@ -2,7 +2,7 @@
 pub struct TableIter<'a> {
     header: Option<&'a Vec<String>>,
     rows: std::slice::Iter<'a, Vec<String>>,
-    footer: Option<&'a Vec<String>>,
+    footer: std::slice::Iter<'a, Vec<String>>,
     state: IterState,
 }
 
@@ -26,10 +26,10 @@ impl<'a> Iterator for TableIter<'a> {
                 self.state = IterState::Footer;
                 self.next()
             }),
-            IterState::Footer => {
+            IterState::Footer => self.footer.next().or_else(|| {
                 self.state = IterState::Done;
-                self.footer
-            }
+                None
+            }),
             IterState::Done => None,
         }
     }
@@ -51,7 +51,7 @@ impl<'a> IntoIterator for &'a super::Table {
         TableIter {
             header: (!self.header.is_empty()).then_some(&self.header),
             rows: self.rows.iter(),
-            footer: (!self.footer.is_empty()).then_some(&self.footer),
+            footer: self.footer.iter(),
             state: initial_state,
         }
     }
@@ -6,6 +6,9 @@ pub use alignment::*;
 mod separator;
 pub use separator::*;
 
+mod style;
+pub use style::TableStyle;
+
 mod columns;
 pub use columns::*;
 
@@ -20,8 +23,10 @@ pub use builder::*;
 pub struct Table {
     header: Vec<String>,        // Stores the header row
     rows: Vec<Vec<String>>,     // Stores the table data
-    footer: Vec<String>,        // Stores the footer row
+    footer: Vec<Vec<String>>,   // Stores the footer rows
     separator: Separator,       // Describes the column and row separators
+    style: TableStyle,          // Describes the borders and rules
+    border_escape: String,      // The ANSI escape sequence the borders and rules are painted with
     columns: Columns,           // Manages column width calculations
     alignments: Vec<Alignment>, // Stores column alignments
 }
@@ -49,9 +54,16 @@ impl Table {
         self
     }
 
-    /// Sets the [footer][Table::footer] row of the table
+    /// Sets the [footer][Table::footer] row of the table, replacing any existing footer rows
     pub fn with_footer(&mut self, footer: Vec<String>) -> &mut Self {
-        self.footer = footer;
+        self.footer = vec![footer];
+        self.columns.mark_for_recalc();
+        self
+    }
+
+    /// Appends an additional row to the [footer][Table::footer] of the table
+    pub fn add_footer_row(&mut self, row: Vec<String>) -> &mut Self {
+        self.footer.push(row);
         self.columns.mark_for_recalc();
         self
     }
@@ -62,6 +74,19 @@ impl Table {
         self
     }
 
+    /// Sets the [style][TableStyle] the table is drawn in
+    pub fn with_style(&mut self, style: TableStyle) -> &mut Self {
+        self.style = style;
+        self.columns.mark_for_recalc();
+        self
+    }
+
+    /// Sets the ANSI escape sequence that the borders and rules are painted with (e.g. `\x1b[2m` to dim them)
+    pub fn with_border_escape(&mut self, escape: impl Into<String>) -> &mut Self {
+        self.border_escape = escape.into();
+        self
+    }
+
     /// Adds a row to the table
     pub fn add_row(&mut self, row: Vec<String>) -> &mut Self {
         self.rows.push(row);
@@ -69,48 +94,86 @@ impl Table {
         self
     }
 
-    /// Builds a vertical separator
-    fn format_vertical_separator(&self) -> String {
-        let sep_v = &self.separator.vertical;
-        let sep_h = &self.separator.horizontal;
-        self.columns
-            .into_iter()
-            .map(|w| sep_v.repeat(*w))
-            .collect::<Vec<_>>()
-            .join(&sep_v.repeat(sep_h.len()))
-            + &sep_v
-            + "\n"
+    /// Draws the given rule (if any) across the columns
+    fn format_rule(&self, rule: Option<&style::Rule>) -> String {
+        let widths = self.columns.into_iter().copied().collect::<Vec<_>>();
+        rule.map(|rule| self.paint_border(&rule.draw(&widths, &self.alignments)) + "\n")
+            .unwrap_or_default()
     }
 
-    /// Formats a single cell with the appropriate alignment
-    fn format_cell(&self, text: &str, width: usize, alignment: Option<&Alignment>) -> String {
-        let visible_width = ansi::visible_width(text);
-        let width = if visible_width < width {
-            text.len() + (width - visible_width)
+    /// Paints the (non-blank) border text with the [border escape sequence][Table::with_border_escape]
+    fn paint_border(&self, text: &str) -> String {
+        if self.border_escape.is_empty() || text.trim().is_empty() {
+            text.to_string()
         } else {
-            width
-        };
-        let res = match alignment {
-            Some(&Alignment::Left) | None => format!("{:<width$}", text, width = width),
-            Some(&Alignment::Center) => format!("{:^width$}", text, width = width),
-            Some(&Alignment::Right) => format!("{:>width$}", text, width = width),
+            format!("{}{}\u{001b}[0m", self.border_escape, text)
+        }
+    }
+
+    /// Formats a single cell with the appropriate alignment.
+    ///
+    /// Padding is computed from the [display width][ansi::visible_width] of the text rather than its
+    /// length, so cells containing ANSI codes, wide (CJK) characters or emoji still line up.
+    fn format_cell(&self, text: &str, width: usize, alignment: Option<&Alignment>) -> String {
+        let padding = width.saturating_sub(ansi::visible_width(text));
+        let (left, right) = match alignment {
+            Some(&Alignment::Left) | None => (0, padding),
+            Some(&Alignment::Center) => (padding / 2, padding - padding / 2),
+            Some(&Alignment::Right) => (padding, 0),
         };
-        res
+        format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
     }
 
-    /// Formats a row of data for display
-    fn format_row(&self, row: &Vec<String>) -> String {
-        let mut res = String::new();
-        for (i, cell) in row.iter().enumerate() {
-            res.push_str(&self.format_cell(
-                cell,
-                self.columns.get_or(i, ansi::visible_width(cell)),
-                self.alignments.get(i),
-            ));
-            res.push_str(&self.separator.horizontal);
-        }
-        res.push_str("\n");
-        res
+    /// Formats a row of data for display, within the borders of the [style][TableStyle].
+    /// Rows with fewer cells than the table has columns are padded with empty cells, so the borders line up.
+    fn format_row(&self, row: &[String], border: &style::Border) -> String {
+        let columns = self.columns.into_iter().count().max(row.len());
+        let cells = (0..columns)
+            .map(|i| {
+                let cell = self
+                    .style
+                    .escape(row.get(i).map(String::as_str).unwrap_or_default());
+                self.format_cell(
+                    &cell,
+                    self.columns.get_or(i, ansi::visible_width(&cell)),
+                    self.alignments.get(i),
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{}{}{}\n",
+            self.paint_border(&border.left),
+            cells.join(&self.paint_border(&border.separator)),
+            self.paint_border(&border.right)
+        )
+    }
+
+    /// The rows of the table (header, rows and footer), with the cells [escaped][TableStyle::escape] for the style
+    fn escaped_rows(&self) -> Vec<Vec<String>> {
+        self.into_iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| self.style.escape(cell).into_owned())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The widths of the columns, i.e. the display width of the widest cell in each
+    pub fn widths(&mut self) -> Vec<usize> {
+        self.columns.calculate(self.escaped_rows());
+        self.columns.into_iter().copied().collect()
+    }
+
+    /// The display width of the widest line of the table, including the borders of the [style][TableStyle]
+    pub fn width(&mut self) -> usize {
+        let widths = self.widths();
+        let border = self.style.border(&self.separator);
+        let separators = widths.len().saturating_sub(1) * ansi::visible_width(&border.separator);
+        ansi::visible_width(&border.left)
+            + widths.iter().sum::<usize>()
+            + separators
+            + ansi::visible_width(&border.right)
     }
 
     /// Generates a formatted string representation of the table
@@ -118,26 +181,31 @@ impl Table {
         let mut res = String::new();
 
         // Calculate column widths
-        let iter = self.into_iter().cloned().collect::<Vec<_>>();
-        self.columns.calculate(iter);
+        self.columns.calculate(self.escaped_rows());
+
+        let border = self.style.border(&self.separator);
+        res.push_str(&self.format_rule(border.top.as_ref()));
 
         // Format Header
         if !self.header.is_empty() {
-            res.push_str(&self.format_row(&self.header));
-            res.push_str(&self.format_vertical_separator());
+            res.push_str(&self.format_row(&self.header, &border));
+            res.push_str(&self.format_rule(border.header.as_ref()));
         }
 
         // Format Rows
         for row in &self.rows {
-            res.push_str(&self.format_row(row));
+            res.push_str(&self.format_row(row, &border));
         }
 
         // Format Footer
         if !self.footer.is_empty() {
-            res.push_str(&self.format_vertical_separator());
-            res.push_str(&self.format_row(&self.footer));
+            res.push_str(&self.format_rule(border.footer.as_ref()));
+            for row in &self.footer {
+                res.push_str(&self.format_row(row, &border));
+            }
         }
 
+        res.push_str(&self.format_rule(border.bottom.as_ref()));
         res
     }
 }
@@ -171,7 +239,17 @@ mod tests {
     fn test_with_footer() {
         let mut table = Table::from_csv("");
         table.with_footer(vec!["Total".to_string(), "42".to_string()]);
-        assert_eq!(table.footer, vec!["Total", "42"]);
+        assert_eq!(table.footer, vec![vec!["Total", "42"]]);
+    }
+
+    #[test]
+    fn test_add_footer_row() {
+        let mut table = Table::from_csv("a,b");
+        table.with_footer(vec!["Total".to_string(), "42".to_string()]);
+        table.add_footer_row(vec!["Mean".to_string(), "4.2".to_string()]);
+        assert_eq!(table.footer, vec![vec!["Total", "42"], vec!["Mean", "4.2"]]);
+        let output = table.display();
+        assert!(output.ends_with("Total    42     \nMean     4.2    \n"));
     }
 
     #[test]
@@ -198,6 +276,50 @@ mod tests {
         assert_eq!(table.rows, vec![vec!["Data1", "Data2"]]);
     }
 
+    #[test]
+    fn test_format_cell_pads_by_display_width() {
+        let table = Table::default();
+        assert_eq!(
+            table.format_cell("日本", 6, Some(&Alignment::Left)),
+            "日本  "
+        );
+        assert_eq!(
+            table.format_cell("日本", 6, Some(&Alignment::Right)),
+            "  日本"
+        );
+        assert_eq!(
+            table.format_cell("日本", 6, Some(&Alignment::Center)),
+            " 日本 "
+        );
+        assert_eq!(
+            table.format_cell("\x1b[31mab\x1b[0m", 4, Some(&Alignment::Right)),
+            "  \x1b[31mab\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_display_aligns_wide_characters() {
+        let mut table = Table::from_tsv("日本語.rs\t1\nmain.rs\t2");
+        table.with_alignments(vec![Alignment::Left, Alignment::Right]);
+        let output = table.display();
+        let widths: Vec<usize> = output.lines().map(ansi::visible_width).collect();
+        assert!(widths.windows(2).all(|w| w[0] == w[1]));
+    }
+
+    #[test]
+    fn test_width_matches_display() {
+        for style in ["plain", "compact", "rounded", "markdown"] {
+            let mut table = Table::from_csv("src/main.rs,12\nREADME.md,3");
+            table
+                .with_header(vec!["Path".to_string(), "Lines".to_string()])
+                .with_style(style.parse().unwrap());
+            assert_eq!(table.widths(), vec![11, 5]);
+            let width = table.width();
+            let output = table.display();
+            assert_eq!(output.lines().map(ansi::visible_width).max(), Some(width));
+        }
+    }
+
     #[test]
     fn test_display_output() {
         let mut table = Table::from_csv("a,b\nc,d");
@@ -0,0 +1,326 @@
+use std::borrow::Cow;
+
+use super::{Alignment, Separator};
+
+/// The named styles a [`Table`][super::Table] can be drawn in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Space-separated columns with dashed rules around the body (customizable with the [`Separator`])
+    #[default]
+    Plain,
+    /// Single-space separated columns with a dashed rule under each column heading
+    Compact,
+    /// A grid drawn with ASCII characters (`+`, `-`, `=` and `|`)
+    AsciiGrid,
+    /// A box drawn with light unicode lines and rounded corners
+    Rounded,
+    /// A box drawn with heavy unicode lines
+    Heavy,
+    /// A markdown-like table, with the column alignments marked in the header rule
+    Markdown,
+}
+
+/// A horizontal rule drawn between the sections of a table
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Rule {
+    /// The string at the start of the rule
+    left: String,
+    /// The string that is repeated to span each column
+    fill: String,
+    /// The string where the rule crosses a column boundary
+    junction: String,
+    /// The string at the end of the rule
+    right: String,
+    /// The extra width of each column (on top of the content) that the fill spans
+    padding: usize,
+    /// Whether to mark the column alignments with colons (as in markdown)
+    aligned: bool,
+}
+
+impl Rule {
+    fn new(left: &str, fill: &str, junction: &str, right: &str, padding: usize) -> Self {
+        Self {
+            left: left.into(),
+            fill: fill.into(),
+            junction: junction.into(),
+            right: right.into(),
+            padding,
+            aligned: false,
+        }
+    }
+
+    /// Draws the rule across columns of the given widths (without a trailing newline)
+    pub(super) fn draw(&self, widths: &[usize], alignments: &[Alignment]) -> String {
+        let segments = widths
+            .iter()
+            .enumerate()
+            .map(|(i, width)| {
+                let mut segment = self.fill.repeat(width + self.padding);
+                if self.aligned && !segment.is_empty() {
+                    match alignments.get(i) {
+                        Some(Alignment::Right) => segment.replace_range(segment.len() - 1.., ":"),
+                        Some(Alignment::Center) => {
+                            segment.replace_range(..1, ":");
+                            segment.replace_range(segment.len() - 1.., ":");
+                        }
+                        Some(Alignment::Left) => segment.replace_range(..1, ":"),
+                        None => {}
+                    }
+                }
+                segment
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{}{}{}",
+            self.left,
+            segments.join(&self.junction),
+            self.right
+        )
+    }
+}
+
+/// The concrete strings used to draw a table in a given [`TableStyle`]
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Border {
+    /// Drawn before the first cell of each row
+    pub(super) left: String,
+    /// Drawn between the cells of each row
+    pub(super) separator: String,
+    /// Drawn after the last cell of each row
+    pub(super) right: String,
+    /// Drawn above the table
+    pub(super) top: Option<Rule>,
+    /// Drawn between the header and the rows
+    pub(super) header: Option<Rule>,
+    /// Drawn between the rows and the footer
+    pub(super) footer: Option<Rule>,
+    /// Drawn below the table
+    pub(super) bottom: Option<Rule>,
+}
+
+impl Border {
+    /// A box with the given vertical line, and the left, fill, junction and right strings of each rule
+    fn boxed(
+        line: &str,
+        top: [&str; 4],
+        header: [&str; 4],
+        footer: [&str; 4],
+        bottom: [&str; 4],
+    ) -> Self {
+        let rule =
+            |[left, fill, junction, right]: [&str; 4]| Rule::new(left, fill, junction, right, 2);
+        Self {
+            left: format!("{line} "),
+            separator: format!(" {line} "),
+            right: format!(" {line}"),
+            top: Some(rule(top)),
+            header: Some(rule(header)),
+            footer: Some(rule(footer)),
+            bottom: Some(rule(bottom)),
+        }
+    }
+}
+
+impl TableStyle {
+    /// The [`Border`] to draw the table with. The [plain][TableStyle::Plain] style is built from the [`Separator`].
+    pub(super) fn border(&self, separator: &Separator) -> Border {
+        match self {
+            Self::Plain => {
+                let rule = Rule::new(
+                    "",
+                    &separator.vertical,
+                    &separator.vertical.repeat(separator.horizontal.len()),
+                    &separator.vertical,
+                    0,
+                );
+                Border {
+                    left: String::new(),
+                    separator: separator.horizontal.clone(),
+                    right: separator.horizontal.clone(),
+                    top: None,
+                    header: Some(rule.clone()),
+                    footer: Some(rule),
+                    bottom: None,
+                }
+            }
+            Self::Compact => {
+                let rule = Rule::new("", "-", " ", "", 0);
+                Border {
+                    left: String::new(),
+                    separator: " ".into(),
+                    right: String::new(),
+                    top: None,
+                    header: Some(rule.clone()),
+                    footer: Some(rule),
+                    bottom: None,
+                }
+            }
+            Self::AsciiGrid => Border::boxed(
+                "|",
+                ["+", "-", "+", "+"],
+                ["+", "=", "+", "+"],
+                ["+", "-", "+", "+"],
+                ["+", "-", "+", "+"],
+            ),
+            Self::Rounded => Border::boxed(
+                "│",
+                ["╭", "─", "┬", "╮"],
+                ["├", "─", "┼", "┤"],
+                ["├", "─", "┼", "┤"],
+                ["╰", "─", "┴", "╯"],
+            ),
+            Self::Heavy => Border::boxed(
+                "┃",
+                ["┏", "━", "┳", "┓"],
+                ["┣", "━", "╋", "┫"],
+                ["┣", "━", "╋", "┫"],
+                ["┗", "━", "┻", "┛"],
+            ),
+            Self::Markdown => Border {
+                left: "| ".into(),
+                separator: " | ".into(),
+                right: " |".into(),
+                top: None,
+                header: Some(Rule {
+                    aligned: true,
+                    ..Rule::new("|", "-", "|", "|", 2)
+                }),
+                footer: None,
+                bottom: None,
+            },
+        }
+    }
+}
+
+impl TableStyle {
+    /// Escapes the characters of the cell that would otherwise be read as part of the borders,
+    /// i.e. the pipes of the [markdown][TableStyle::Markdown] tables
+    pub(super) fn escape<'a>(&self, cell: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Markdown if cell.contains('|') => Cow::Owned(cell.replace('|', "\\|")),
+            _ => Cow::Borrowed(cell),
+        }
+    }
+}
+
+impl std::str::FromStr for TableStyle {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" | "default" => Ok(Self::Plain),
+            "compact" => Ok(Self::Compact),
+            "ascii-grid" | "ascii" | "grid" => Ok(Self::AsciiGrid),
+            "rounded" | "unicode" | "box" => Ok(Self::Rounded),
+            "heavy" | "bold" => Ok(Self::Heavy),
+            "markdown" | "md" => Ok(Self::Markdown),
+            x => Err(format!("Invalid table style: {x}")),
+        }
+    }
+}
+
+impl std::fmt::Display for TableStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Plain => "plain",
+            Self::Compact => "compact",
+            Self::AsciiGrid => "ascii-grid",
+            Self::Rounded => "rounded",
+            Self::Heavy => "heavy",
+            Self::Markdown => "markdown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+
+    fn sample(style: TableStyle) -> String {
+        let mut table = Table::from_csv("a,1\nbb,22");
+        table
+            .with_header(vec!["H".to_string(), "N".to_string()])
+            .with_footer(vec!["T".to_string(), "23".to_string()])
+            .with_alignments(vec![Alignment::Left, Alignment::Right])
+            .with_style(style);
+        table.display()
+    }
+
+    #[test]
+    fn test_parse_table_style() {
+        assert_eq!("ascii".parse(), Ok(TableStyle::AsciiGrid));
+        assert_eq!("Rounded".parse(), Ok(TableStyle::Rounded));
+        assert!("fancy".parse::<TableStyle>().is_err());
+        assert_eq!(TableStyle::AsciiGrid.to_string(), "ascii-grid");
+    }
+
+    #[test]
+    fn test_ascii_grid_style() {
+        let expected = "\
++----+----+
+| H  |  N |
++====+====+
+| a  |  1 |
+| bb | 22 |
++----+----+
+| T  | 23 |
++----+----+
+";
+        assert_eq!(sample(TableStyle::AsciiGrid), expected);
+    }
+
+    #[test]
+    fn test_rounded_style() {
+        let output = sample(TableStyle::Rounded);
+        assert!(output.starts_with("╭────┬────╮\n│ H  │  N │\n├────┼────┤\n"));
+        assert!(output.ends_with("│ T  │ 23 │\n╰────┴────╯\n"));
+        let widths: Vec<usize> = output.lines().map(ansi::visible_width).collect();
+        assert!(widths.windows(2).all(|w| w[0] == w[1]));
+    }
+
+    #[test]
+    fn test_markdown_style() {
+        let expected = "\
+| H  |  N |
+|:---|---:|
+| a  |  1 |
+| bb | 22 |
+| T  | 23 |
+";
+        assert_eq!(sample(TableStyle::Markdown), expected);
+    }
+
+    #[test]
+    fn test_markdown_escapes_pipes() {
+        let mut table = Table::from_tsv("a|b.rs\t1\nc.rs\t2");
+        table.with_style(TableStyle::Markdown);
+        let expected = "\
+| a\\|b.rs | 1 |
+| c.rs    | 2 |
+";
+        assert_eq!(table.display(), expected);
+
+        // The other styles show the cells as they are
+        let mut table = Table::from_tsv("a|b.rs\t1");
+        table.with_style(TableStyle::AsciiGrid);
+        assert!(table.display().contains("| a|b.rs | 1 |"));
+    }
+
+    #[test]
+    fn test_border_escape() {
+        let mut table = Table::from_csv("a,1");
+        table
+            .with_style(TableStyle::AsciiGrid)
+            .with_border_escape("\x1b[2m");
+        let output = table.display();
+        assert!(output.starts_with("\x1b[2m+---+---+\x1b[0m\n\x1b[2m| \x1b[0ma"));
+        let widths: Vec<usize> = output.lines().map(ansi::visible_width).collect();
+        assert_eq!(widths, vec![9, 9, 9]);
+    }
+
+    #[test]
+    fn test_compact_style() {
+        let expected = "H   N\n-- --\na   1\nbb 22\n-- --\nT  23\n";
+        assert_eq!(sample(TableStyle::Compact), expected);
+    }
+}
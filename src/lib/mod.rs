@@ -1,3 +1,43 @@
+//! Count the number of lines, words, characters and bytes in files, and report them as tables, charts or
+//! structured data.
+//!
+//! The [`scanner`] reads the files into [`ScanResults`][scanner::ScanResults], and the [`output`] module renders
+//! them in one of the output [formats][output::Format], as configured by an [output configuration][output::Config].
+//!
+//! ```
+//! use tali::{
+//!     output::{self, Column, Config, Format},
+//!     scanner::Scanner,
+//! };
+//!
+//! let (results, _) = Scanner::new().scan(&["src"]);
+//! let config = Config {
+//!     columns: vec![Column::Path, Column::Lines],
+//!     format: Format::CSV,
+//!     ..Default::default()
+//! };
+//!
+//! let mut csv = Vec::new();
+//! output::write(results, config, &mut csv)?;
+//! assert!(csv.starts_with(b"Path,Lines\n"));
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! The [`Table`] the reports are drawn with can also be used on its own:
+//!
+//! ```
+//! use tali::Table;
+//!
+//! let mut table = Table::from_csv("Language,Files\nRust,12\nMarkdown,3");
+//! assert!(table.display().contains("Markdown"));
+//! ```
+
+mod error;
+pub use error::{Error, Result};
 mod helpers;
+pub use helpers::{
+    language::{self, Language},
+    table::{self, Table},
+};
 pub mod output;
 pub mod scanner;
@@ -0,0 +1,364 @@
+use std::io::{self, Write};
+
+use serde_json::{Value, json};
+
+use crate::{
+    helpers::path,
+    scanner::{File, ScanResults},
+};
+
+use super::{Config, Format, Formatter, structured::escape_xml};
+
+/// A threshold of the check mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// The [maximum line length][Config::max_line_length]
+    MaxLineLength,
+    /// The [maximum number of lines per file][Config::max_file_lines]
+    MaxFileLines,
+}
+
+impl Rule {
+    const ALL: [Rule; 2] = [Rule::MaxLineLength, Rule::MaxFileLines];
+
+    /// The identifier of the rule in the reports, e.g. `max-line-length`
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::MaxLineLength => "max-line-length",
+            Rule::MaxFileLines => "max-file-lines",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Rule::MaxLineLength => "Lines must not exceed the maximum line length",
+            Rule::MaxFileLines => "Files must not exceed the maximum number of lines",
+        }
+    }
+
+    /// The configured limit of the rule, if it is enabled
+    fn limit(&self, config: &Config) -> Option<usize> {
+        match self {
+            Rule::MaxLineLength => config.max_line_length,
+            Rule::MaxFileLines => config.max_file_lines,
+        }
+    }
+}
+
+/// A file (or a line of it) exceeding one of the thresholds of the check mode
+#[derive(Debug, Clone)]
+pub struct Violation<'a> {
+    pub file: &'a File,
+    pub rule: Rule,
+    /// The (1-based) line the violation is reported at. For the files that are too long, the first line over the limit.
+    pub line: usize,
+    pub message: String,
+}
+
+/// Evaluates the thresholds of the check mode over the files, in order
+pub fn violations<'a>(results: &'a ScanResults, config: &Config) -> Vec<Violation<'a>> {
+    let mut violations = Vec::new();
+    for file in &results.files {
+        if let Some(limit) = config.max_file_lines.filter(|limit| file.lines > *limit) {
+            violations.push(Violation {
+                file,
+                rule: Rule::MaxFileLines,
+                line: limit + 1,
+                message: format!(
+                    "file is {} lines long, exceeding the limit of {}",
+                    file.lines, limit
+                ),
+            });
+        }
+        if let Some(limit) = config.max_line_length {
+            for long_line in &file.long_lines {
+                violations.push(Violation {
+                    file,
+                    rule: Rule::MaxLineLength,
+                    line: long_line.line,
+                    message: format!(
+                        "line is {} characters long ({} columns), exceeding the limit of {}",
+                        long_line.length, long_line.width, limit
+                    ),
+                });
+            }
+        }
+    }
+    violations
+}
+
+/// Lists the lines and files that exceed the configured thresholds (the [maximum line length][Config::max_line_length]
+/// and the [maximum number of lines per file][Config::max_file_lines]). By default in a `path:line: message` format
+/// that editors and CI logs can link to, or as a [SARIF][Format::SARIF] or [checkstyle][Format::Checkstyle] report
+/// that code-scanning tools display inline.
+#[derive(Debug, Default)]
+pub struct CheckFormatter {}
+
+impl Formatter for CheckFormatter {
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let violations = violations(results, config);
+        match config.format {
+            Format::SARIF => {
+                serde_json::to_writer_pretty(writer, &self.sarif(&violations, config))?;
+                Ok(())
+            }
+            Format::Checkstyle => writer.write_all(self.checkstyle(&violations).as_bytes()),
+            _ => self.text(&violations, config, writer),
+        }
+    }
+}
+
+impl CheckFormatter {
+    fn text(
+        &self,
+        violations: &[Violation],
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        for violation in violations {
+            writeln!(
+                writer,
+                "{}:{}: {}",
+                path::display(&violation.file.path),
+                violation.line,
+                violation.message
+            )?;
+        }
+
+        if config.footer {
+            let mut summaries = Vec::new();
+            if let Some(limit) = config.max_file_lines {
+                let files = self.count(violations, Rule::MaxFileLines).0;
+                summaries.push(format!("{files} file(s) exceed the limit of {limit} lines"));
+            }
+            if let Some(limit) = config.max_line_length {
+                let (lines, files) = self.count(violations, Rule::MaxLineLength);
+                summaries.push(format!(
+                    "{lines} line(s) in {files} file(s) exceed the limit of {limit} characters"
+                ));
+            }
+            writer.write_all(summaries.join("\n").as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// The number of violations of the rule, and the number of files with any
+    fn count(&self, violations: &[Violation], rule: Rule) -> (usize, usize) {
+        let (mut count, mut files) = (0, 0);
+        let mut previous: Option<&File> = None;
+        for violation in violations.iter().filter(|v| v.rule == rule) {
+            count += 1;
+            if previous.is_none_or(|file| !std::ptr::eq(file, violation.file)) {
+                files += 1;
+            }
+            previous = Some(violation.file);
+        }
+        (count, files)
+    }
+
+    /// A SARIF 2.1.0 log with a single run, listing the enabled rules and their violations
+    fn sarif(&self, violations: &[Violation], config: &Config) -> Value {
+        let rules = Rule::ALL
+            .into_iter()
+            .filter(|rule| rule.limit(config).is_some())
+            .collect::<Vec<_>>();
+        let results = violations
+            .iter()
+            .map(|violation| {
+                json!({
+                    "ruleId": violation.rule.id(),
+                    "ruleIndex": rules.iter().position(|rule| *rule == violation.rule),
+                    "level": "error",
+                    "message": { "text": violation.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": uri(&path::display(&violation.file.path)) },
+                            "region": { "startLine": violation.line },
+                        }
+                    }],
+                })
+            })
+            .collect::<Vec<_>>();
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                json!({
+                    "id": rule.id(),
+                    "shortDescription": { "text": rule.description() },
+                    "properties": { "limit": rule.limit(config) },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "tali",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    }
+                },
+                "results": results,
+            }],
+        })
+    }
+
+    /// A checkstyle XML report, with the violations grouped by file
+    fn checkstyle(&self, violations: &[Violation]) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<checkstyle version=\"4.3\">\n",
+        );
+        let mut previous: Option<&File> = None;
+        for violation in violations {
+            if previous.is_none_or(|file| !std::ptr::eq(file, violation.file)) {
+                if previous.is_some() {
+                    xml.push_str("  </file>\n");
+                }
+                xml.push_str(&format!(
+                    "  <file name=\"{}\">\n",
+                    escape_xml(&path::display(&violation.file.path))
+                ));
+            }
+            xml.push_str(&format!(
+                "    <error line=\"{}\" severity=\"error\" message=\"{}\" source=\"tali.{}\"/>\n",
+                violation.line,
+                escape_xml(&violation.message),
+                violation.rule.id()
+            ));
+            previous = Some(violation.file);
+        }
+        if previous.is_some() {
+            xml.push_str("  </file>\n");
+        }
+        xml.push_str("</checkstyle>\n");
+        xml
+    }
+}
+
+/// Encodes the path as a relative URI reference, percent-encoding everything but the unreserved characters
+fn uri(path: &str) -> String {
+    let mut uri = String::with_capacity(path.len());
+    for byte in path.replace('\\', "/").bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            byte => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> ScanResults {
+        let mut results = ScanResults::default();
+        for (path, contents) in [
+            ("short.rs", "ok"),
+            ("a <b>.rs", "ok\nthis line is too long\nok\nok"),
+        ] {
+            let reader = std::io::Cursor::new(contents);
+            let mut file = File::scan_reader_with_limit(reader, Some(10)).unwrap();
+            file.path = path.into();
+            results.files.push(file);
+        }
+        results
+    }
+
+    fn config(format: Format) -> Config {
+        Config {
+            max_line_length: Some(10),
+            max_file_lines: Some(3),
+            format,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lists_long_lines() {
+        let reader = std::io::Cursor::new("ok\nthis line is too long\nok");
+        let file = File::scan_reader_with_limit(reader, Some(10)).unwrap();
+        let results = ScanResults {
+            files: vec![file],
+            ..Default::default()
+        };
+        let config = Config {
+            max_line_length: Some(10),
+            ..Default::default()
+        };
+
+        let output = CheckFormatter::default().format(&results, &config);
+
+        assert_eq!(
+            output,
+            "STDIN:2: line is 21 characters long (21 columns), exceeding the limit of 10\n\
+             1 line(s) in 1 file(s) exceed the limit of 10 characters"
+        );
+    }
+
+    #[test]
+    fn test_lists_long_files() {
+        let output = CheckFormatter::default().format(&results(), &config(Format::Table));
+        assert_eq!(
+            output,
+            "a <b>.rs:4: file is 4 lines long, exceeding the limit of 3\n\
+             a <b>.rs:2: line is 21 characters long (21 columns), exceeding the limit of 10\n\
+             1 file(s) exceed the limit of 3 lines\n\
+             1 line(s) in 1 file(s) exceed the limit of 10 characters"
+        );
+    }
+
+    #[test]
+    fn test_sarif() {
+        let output = CheckFormatter::default().format(&results(), &config(Format::SARIF));
+        let sarif: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "max-line-length");
+        assert_eq!(run["tool"]["driver"]["rules"][1]["properties"]["limit"], 3);
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "max-file-lines");
+        assert_eq!(results[0]["ruleIndex"], 1);
+        let location = &results[1]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "a%20%3Cb%3E.rs");
+        assert_eq!(location["region"]["startLine"], 2);
+    }
+
+    #[test]
+    fn test_checkstyle() {
+        let output = CheckFormatter::default().format(&results(), &config(Format::Checkstyle));
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<checkstyle version="4.3">
+  <file name="a &lt;b&gt;.rs">
+    <error line="4" severity="error" message="file is 4 lines long, exceeding the limit of 3" source="tali.max-file-lines"/>
+    <error line="2" severity="error" message="line is 21 characters long (21 columns), exceeding the limit of 10" source="tali.max-line-length"/>
+  </file>
+</checkstyle>
+"#;
+        assert_eq!(output, expected);
+
+        let clean = Config {
+            max_line_length: None,
+            max_file_lines: None,
+            ..config(Format::Checkstyle)
+        };
+        assert!(
+            CheckFormatter::default()
+                .format(&results(), &clean)
+                .ends_with("<checkstyle version=\"4.3\">\n</checkstyle>\n")
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use crate::{
+    helpers::{number, path, table::Alignment},
+    scanner::{File, ScanResults},
+};
+
+use super::{Config, Format, Metric, format_stat};
+
+/// A column in the output. The [`Config`] holds the selected columns in the order they are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Column {
+    /// The language of the file
+    Language,
+    /// The path of the file (or the number of files when grouped by language)
+    Path,
+    Lines,
+    Words,
+    Chars,
+    Graphemes,
+    Width,
+    Bytes,
+    MaxLineLength,
+    MaxLineWidth,
+    AvgLineLength,
+    /// The share of the [total][ScanResults::total] that the row accounts for, in the given metric
+    Percent(Metric),
+    /// The graphical visualization of the [graph metric][Config::graph_by]
+    Graph,
+}
+
+impl Column {
+    /// The columns shown when none are explicitly selected
+    pub const DEFAULT: [Column; 7] = [
+        Column::Language,
+        Column::Path,
+        Column::Lines,
+        Column::Words,
+        Column::Chars,
+        Column::Bytes,
+        Column::Graph,
+    ];
+
+    /// The numeric [`Metric`] displayed in this column, if any
+    pub fn metric(&self) -> Option<Metric> {
+        match self {
+            Self::Lines => Some(Metric::Lines),
+            Self::Words => Some(Metric::Words),
+            Self::Chars => Some(Metric::Chars),
+            Self::Graphemes => Some(Metric::Graphemes),
+            Self::Width => Some(Metric::Width),
+            Self::Bytes => Some(Metric::Bytes),
+            Self::MaxLineLength => Some(Metric::MaxLineLength),
+            Self::MaxLineWidth => Some(Metric::MaxLineWidth),
+            Self::AvgLineLength => Some(Metric::AvgLineLength),
+            Self::Language | Self::Path | Self::Percent(_) | Self::Graph => None,
+        }
+    }
+
+    /// The percentage of the total that the file accounts for, if this is a [percentage][Column::Percent] column
+    pub fn percentage(&self, file: &File, results: &ScanResults) -> Option<f64> {
+        match self {
+            Self::Percent(metric) => {
+                let total = results.total.value(*metric).unwrap_or_default();
+                let value = file.value(*metric).unwrap_or_default();
+                Some(if total == 0.0 {
+                    0.0
+                } else {
+                    value / total * 100.0
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// How important the column is when the table has to be narrowed to fit the terminal.
+    /// The columns with the lowest priority are dropped first.
+    pub fn priority(&self) -> usize {
+        match self {
+            Self::Graph => 0,
+            Self::Percent(_) => 1,
+            Self::Graphemes => 2,
+            Self::Width => 3,
+            Self::MaxLineWidth => 4,
+            Self::AvgLineLength => 5,
+            Self::MaxLineLength => 6,
+            Self::Chars => 7,
+            Self::Words => 8,
+            Self::Language => 9,
+            Self::Bytes => 10,
+            Self::Lines => 11,
+            Self::Path => 12,
+        }
+    }
+
+    /// Whether the column holds data (as opposed to the [graph][Column::Graph], which is purely visual)
+    pub fn is_data(&self) -> bool {
+        *self != Self::Graph
+    }
+
+    /// The title of the column shown in the header row
+    pub fn header(&self, config: &Config) -> String {
+        let title = match self {
+            Self::Percent(metric) => {
+                let title = Column::from(*metric).header(config);
+                return format!("{} %", title);
+            }
+            Self::Language => "Language",
+            Self::Path if config.group_by_language => "Files",
+            Self::Path => "Path",
+            Self::Lines => "Lines",
+            Self::Words => "Words",
+            Self::Chars => "Chars",
+            Self::Graphemes => "Graphemes",
+            Self::Width => "Width",
+            Self::Bytes => "Bytes",
+            Self::MaxLineLength => "Max Line",
+            Self::MaxLineWidth => "Max Width",
+            Self::AvgLineLength => "Avg Line",
+            Self::Graph => "Graph",
+        };
+        title.to_string()
+    }
+
+    /// The key used for the column in structured formats (like JSON)
+    pub fn key(&self, config: &Config) -> String {
+        match self {
+            Self::Path if config.group_by_language => "files".to_string(),
+            Self::Percent(metric) => format!("{}_percent", metric).replace('-', "_"),
+            _ => self.to_string().replace('-', "_"),
+        }
+    }
+
+    /// The alignment of the column in the table
+    pub fn alignment(&self, config: &Config) -> Alignment {
+        match self {
+            Self::Path if !config.group_by_language => Alignment::Left,
+            Self::Graph => Alignment::Left,
+            _ => Alignment::Right,
+        }
+    }
+
+    /// The (uncolored) value of the column for the given [`File`]. The [graph][Column::Graph] has no value.
+    pub fn value(&self, file: &File, results: &ScanResults, config: &Config) -> String {
+        match self {
+            Self::Language => file.language.to_string(),
+            Self::Path if config.group_by_language => format_count(file.count as u64, config),
+            Self::Path => path::display(&file.path),
+            Self::Percent(_) => format_percent(self.percentage(file, results), config),
+            Self::Graph => String::new(),
+            column => {
+                let value = file.value(column.metric().unwrap_or(Metric::Bytes));
+                column.format(value, config)
+            }
+        }
+    }
+
+    /// The value of the column in the footer row. Columns that cannot be summed show
+    /// the max value across the results (or the overall average for the average line length).
+    pub fn total(&self, results: &ScanResults, config: &Config) -> String {
+        let total = &results.total;
+        match self {
+            Self::Language => "Total".to_string(),
+            Self::Path => format_count(total.files as u64, config),
+            Self::Percent(_) => format_percent(Some(100.0), config),
+            Self::Graph => String::new(),
+            Self::AvgLineLength if total.lines > 0 => {
+                format_decimal(total.chars as f64 / total.lines as f64, config)
+            }
+            column => {
+                let metric = column.metric().unwrap_or(Metric::Bytes);
+                column.format(total.value(metric).or(results.max.value(metric)), config)
+            }
+        }
+    }
+
+    /// The value of the column in the "Others" row for the aggregate of the filtered out files
+    pub fn others(&self, others: &File, results: &ScanResults, config: &Config) -> String {
+        match self {
+            Self::Language => "Others".to_string(),
+            Self::Path => format_count(others.count as u64, config),
+            column => column.value(others, results, config),
+        }
+    }
+
+    /// Formats a statistic of the column's metric (e.g. the mean), in human-readable form if [configured][Config::humanize]
+    pub fn stat(&self, value: f64, config: &Config) -> String {
+        match self {
+            Self::Bytes if config.humanize() => {
+                number::format_bytes(value.round() as u64, config.si, config.thousands_separator)
+            }
+            _ => format_decimal(value, config),
+        }
+    }
+
+    /// Formats a numeric value of the column, in human-readable form if [configured][Config::humanize]
+    fn format(&self, value: Option<f64>, config: &Config) -> String {
+        match (self, value) {
+            (_, None) => String::new(),
+            (Self::AvgLineLength, Some(value)) => format_decimal(value, config),
+            (Self::Bytes, Some(value)) if config.humanize() => {
+                number::format_bytes(value as u64, config.si, config.thousands_separator)
+            }
+            (_, Some(value)) => format_count(value as u64, config),
+        }
+    }
+}
+
+/// Formats a count, with thousands separators if [configured][Config::humanize]
+fn format_count(value: u64, config: &Config) -> String {
+    if config.humanize() {
+        number::group_digits(value, config.thousands_separator)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats a decimal number (like a statistic) with up to two decimal places, and thousands separators if
+/// [configured][Config::humanize]
+fn format_decimal(value: f64, config: &Config) -> String {
+    let value = format_stat(value);
+    if config.humanize() {
+        number::group_decimal(&value, config.thousands_separator)
+    } else {
+        value
+    }
+}
+
+/// Formats a percentage with the [configured precision][Config::percent_precision].
+/// The `%` sign is only added in the formats for humans (or wherever the numbers are [humanized][Config::humanize]),
+/// so that the machine formats hold plain numbers.
+fn format_percent(value: Option<f64>, config: &Config) -> String {
+    let value = format!("{:.*}", config.percent_precision, value.unwrap_or_default());
+    let value = if config.humanize() {
+        number::group_decimal(&value, config.thousands_separator)
+    } else {
+        value
+    };
+    match config.format {
+        Format::Table | Format::Plain | Format::HTML => value + "%",
+        _ if config.humanize() => value + "%",
+        _ => value,
+    }
+}
+
+impl From<Metric> for Column {
+    /// The column that displays the given metric
+    fn from(metric: Metric) -> Self {
+        match metric {
+            Metric::Language => Self::Language,
+            Metric::Path | Metric::Files => Self::Path,
+            Metric::Lines => Self::Lines,
+            Metric::Words => Self::Words,
+            Metric::Chars => Self::Chars,
+            Metric::Graphemes => Self::Graphemes,
+            Metric::Width => Self::Width,
+            Metric::Bytes => Self::Bytes,
+            Metric::MaxLineLength => Self::MaxLineLength,
+            Metric::MaxLineWidth => Self::MaxLineWidth,
+            Metric::AvgLineLength => Self::AvgLineLength,
+        }
+    }
+}
+
+impl std::str::FromStr for Column {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Percentage columns are written as the metric followed by a `%` (e.g. `lines%`)
+        if let Some(metric) = s.trim().strip_suffix('%') {
+            return match metric.parse::<Metric>() {
+                Ok(metric) if metric.is_additive() => Ok(Self::Percent(metric)),
+                _ => Err(format!("Invalid percentage column: {}", s)),
+            };
+        }
+        match s.trim().to_lowercase().as_str() {
+            "graph" | "g" | "bar" | "vis" | "visualization" => Ok(Self::Graph),
+            "path" | "paths" | "p" | "files" | "file" | "f" | "count" => Ok(Self::Path),
+            x => x
+                .parse::<Metric>()
+                .map(Column::from)
+                .map_err(|_| format!("Invalid column: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Language => write!(f, "language"),
+            Self::Path => write!(f, "path"),
+            Self::Percent(metric) => write!(f, "{}%", metric),
+            Self::Graph => write!(f, "graph"),
+            column => write!(f, "{}", column.metric().unwrap_or(Metric::Bytes)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::Human;
+
+    #[test]
+    fn test_parse_columns() {
+        let columns: Result<Vec<Column>, _> = "path,lines,graph,bytes"
+            .split(',')
+            .map(str::parse)
+            .collect();
+        assert_eq!(
+            columns,
+            Ok(vec![
+                Column::Path,
+                Column::Lines,
+                Column::Graph,
+                Column::Bytes
+            ])
+        );
+        assert!("colour".parse::<Column>().is_err());
+    }
+
+    #[test]
+    fn test_header_and_alignment_depend_on_grouping() {
+        let mut config = Config::default();
+        assert_eq!(Column::Path.header(&config), "Path");
+        assert_eq!(Column::Path.alignment(&config), Alignment::Left);
+
+        config.group_by_language = true;
+        assert_eq!(Column::Path.header(&config), "Files");
+        assert_eq!(Column::Path.alignment(&config), Alignment::Right);
+    }
+
+    #[test]
+    fn test_values() {
+        let file = File::scan_reader(std::io::Cursor::new("one two\nthree")).unwrap();
+        let config = Config::default();
+        let results = ScanResults::default();
+        assert_eq!(Column::Path.value(&file, &results, &config), "STDIN");
+        assert_eq!(Column::Words.value(&file, &results, &config), "3");
+        assert_eq!(Column::AvgLineLength.value(&file, &results, &config), "6");
+        assert_eq!(Column::Graph.value(&file, &results, &config), "");
+    }
+
+    #[test]
+    fn test_percent_columns() {
+        assert_eq!("lines%".parse(), Ok(Column::Percent(Metric::Lines)));
+        assert_eq!("files%".parse(), Ok(Column::Percent(Metric::Files)));
+        assert!("max-line-length%".parse::<Column>().is_err());
+
+        let file = File::scan_reader(std::io::Cursor::new("one two\nthree")).unwrap();
+        let mut results = ScanResults::default();
+        results.total.add(&file);
+        results.total.add(&file);
+        results.total.add(&file);
+
+        let mut config = Config {
+            percent_precision: 2,
+            ..Default::default()
+        };
+        let column = Column::Percent(Metric::Words);
+        assert_eq!(column.header(&config), "Words %");
+        assert_eq!(column.key(&config), "words_percent");
+        assert_eq!(column.value(&file, &results, &config), "33.33%");
+        assert_eq!(column.total(&results, &config), "100.00%");
+
+        config.format = Format::CSV;
+        assert_eq!(column.value(&file, &results, &config), "33.33");
+        assert_eq!(
+            column.value(&file, &ScanResults::default(), &config),
+            "0.00"
+        );
+    }
+
+    #[test]
+    fn test_human_readable_values() {
+        let mut results = ScanResults::default();
+        results.total.files = 1234;
+        results.total.bytes = 1536;
+        let mut config = Config {
+            human: Some(Human::Table),
+            ..Default::default()
+        };
+        assert_eq!(Column::Path.total(&results, &config), "1,234");
+        assert_eq!(Column::Bytes.total(&results, &config), "1.5 KiB");
+
+        config.si = true;
+        config.thousands_separator = '.';
+        assert_eq!(Column::Path.total(&results, &config), "1.234");
+        // The sizes use the same decimal mark as the other numbers
+        assert_eq!(Column::Bytes.total(&results, &config), "1,5 kB");
+
+        // Machine formats keep the raw integers unless requested otherwise
+        config.format = Format::CSV;
+        assert_eq!(Column::Bytes.total(&results, &config), "1536");
+        config.human = Some(Human::All);
+        assert_eq!(Column::Bytes.total(&results, &config), "1,5 kB");
+        assert_eq!(
+            Column::Percent(Metric::Bytes).total(&results, &config),
+            "100,0%"
+        );
+        assert_eq!(Column::Lines.stat(12345.678, &config), "12.345,68");
+        assert_eq!(Column::Bytes.stat(1499.6, &config), "1,5 kB");
+    }
+}
@@ -1,10 +1,14 @@
-use crate::{
-    helpers::path,
-    scanner::{File, ScanResults},
+use std::{
+    borrow::Cow,
+    io::{self, Write},
 };
 
-use super::{Config, Formatter};
+use crate::scanner::{File, ScanResults};
 
+use super::{Config, Formatter, RowFormatter, stats_rows};
+
+/// Renders the results as delimiter-separated values (like CSV), following RFC 4180: the fields that
+/// contain the delimiter, a quote or a line break are quoted, and the quotes within them are doubled.
 #[derive(Debug, Default)]
 pub struct DelimiterFormatter<'a> {
     delimiter: &'a str,
@@ -17,109 +21,217 @@ impl<'a> DelimiterFormatter<'a> {
 }
 
 impl Formatter for DelimiterFormatter<'_> {
-    fn format(&self, results: &ScanResults, config: &Config) -> String {
-        let mut res = String::new();
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        self.write_header(config, writer)?;
 
-        if config.header {
-            res.push_str(&self.build_header(config));
+        for file in &results.files {
+            self.write_row(file, results, config, writer)?;
         }
 
-        for file in &results.files {
-            res.push_str(&self.build_row(file, results, config));
+        if let Some(others) = &results.others {
+            writer.write_all(self.build_others_row(others, results, config).as_bytes())?;
         }
 
-        if config.footer {
-            res.push_str(&self.build_footer(results, config));
+        self.write_footer(results, config, writer)
+    }
+}
+
+impl RowFormatter for DelimiterFormatter<'_> {
+    fn write_header(&self, config: &Config, writer: &mut dyn Write) -> io::Result<()> {
+        if config.header {
+            writer.write_all(self.build_header(config).as_bytes())?;
         }
+        Ok(())
+    }
 
-        res
+    fn write_row(
+        &self,
+        file: &File,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        writer.write_all(self.build_row(file, results, config).as_bytes())
+    }
+
+    fn write_footer(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        if config.footer_in_data {
+            writer.write_all(self.build_footer_rows(results, config).as_bytes())?;
+        }
+        Ok(())
     }
 }
 
 impl DelimiterFormatter<'_> {
-    // Helper function to select columns
-    fn selected_columns<T>(&self, values: T, config: &Config) -> Vec<T::Item>
-    where
-        T: IntoIterator,
-        T::IntoIter: ExactSizeIterator,
-        T::Item: Clone,
-    {
-        let options = [
-            config.language,
-            config.files,
-            config.lines,
-            config.words,
-            config.chars,
-            config.bytes,
-        ];
-        values
-            .into_iter()
-            .enumerate()
-            .filter_map(|(i, v)| options[i].then_some(v.clone()))
-            .collect()
+    /// The totals (and the statistics, if enabled) on their own, with the header. Used when the
+    /// [footer is kept out of the data][Config::footer_in_data], so that the data imports cleanly.
+    pub fn totals(&self, results: &ScanResults, config: &Config) -> String {
+        let mut res = String::new();
+        if config.header {
+            res.push_str(&self.build_header(config));
+        }
+        res.push_str(&self.build_footer_rows(results, config));
+        res
     }
 
     fn build_header(&self, config: &Config) -> String {
-        let files: &str = if config.group_by_language {
-            "Files"
-        } else {
-            "Path"
-        };
-        self.selected_columns(
-            ["Language", files, "Lines", "Words", "Chars", "Bytes"].map(String::from),
-            config,
-        )
-        .join(self.delimiter)
-            + "\n"
+        let fields = config.data_columns().map(|column| column.header(config));
+        record(fields, self.delimiter, config)
     }
 
-    fn build_row(&self, file: &File, _results: &ScanResults, config: &Config) -> String {
-        let mut cols = Vec::new();
+    fn build_row(&self, file: &File, results: &ScanResults, config: &Config) -> String {
+        let fields = config
+            .data_columns()
+            .map(|column| column.value(file, results, config));
+        record(fields, self.delimiter, config)
+    }
 
-        if config.language {
-            cols.push(file.language.to_string());
-        }
+    fn build_others_row(&self, others: &File, results: &ScanResults, config: &Config) -> String {
+        let fields = config
+            .data_columns()
+            .map(|column| column.others(others, results, config));
+        record(fields, self.delimiter, config)
+    }
 
-        if config.files {
-            let file = if config.group_by_language {
-                file.count.to_string()
-            } else {
-                path::display(&file.path)
-            };
-            cols.push(file);
-        }
+    /// The total row and the statistics rows, as enabled
+    fn build_footer_rows(&self, results: &ScanResults, config: &Config) -> String {
+        let mut res = String::new();
 
-        if config.lines {
-            cols.push(file.lines.to_string());
+        if config.footer {
+            let fields = config
+                .data_columns()
+                .map(|column| column.total(results, config));
+            res.push_str(&record(fields, self.delimiter, config));
         }
 
-        if config.words {
-            cols.push(file.words.to_string());
+        if config.stats {
+            for row in stats_rows(results, config.data_columns(), config) {
+                res.push_str(&record(row, self.delimiter, config));
+            }
         }
 
-        if config.chars {
-            cols.push(file.chars.to_string());
-        }
+        res
+    }
+}
 
-        if config.bytes {
-            cols.push(file.bytes.to_string());
+/// Builds a record of the fields, separated by the delimiter and ended with the [line terminator][Config::line_terminator]
+pub(super) fn record<S: AsRef<str>>(
+    fields: impl IntoIterator<Item = S>,
+    delimiter: &str,
+    config: &Config,
+) -> String {
+    let fields = fields
+        .into_iter()
+        .map(|field| quote(field.as_ref(), delimiter).into_owned())
+        .collect::<Vec<_>>();
+    fields.join(delimiter) + config.line_terminator.as_str()
+}
+
+/// Quotes the field if it contains the delimiter, a quote or a line break, doubling the quotes within it
+fn quote<'a>(field: &'a str, delimiter: &str) -> Cow<'a, str> {
+    let needs_quotes =
+        field.contains(['"', '\n', '\r']) || (!delimiter.is_empty() && field.contains(delimiter));
+    if needs_quotes {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{Column, LineTerminator, scan_contents};
+
+    fn results() -> ScanResults {
+        let paths = [
+            "plain.rs",
+            "with,comma.rs",
+            "with \"quotes\".rs",
+            "with\nnewline.rs",
+        ];
+        scan_contents(paths.map(|path| (path, "one two\nthree")))
+    }
+
+    fn config() -> Config {
+        Config {
+            columns: vec![Column::Path, Column::Lines],
+            ..Default::default()
         }
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("plain", ","), "plain");
+        assert_eq!(quote("a,b", ","), "\"a,b\"");
+        assert_eq!(quote("a,b", "\t"), "a,b");
+        assert_eq!(quote("say \"hi\"", ","), "\"say \"\"hi\"\"\"");
+        assert_eq!(quote("two\r\nlines", ";"), "\"two\r\nlines\"");
+        assert_eq!(quote("a::b", "::"), "\"a::b\"");
+    }
+
+    #[test]
+    fn test_csv_is_rfc_4180_compliant() {
+        let output = DelimiterFormatter::with(",").format(&results(), &config());
+        let expected = "\
+Path,Lines
+plain.rs,2
+\"with,comma.rs\",2
+\"with \"\"quotes\"\".rs\",2
+\"with
+newline.rs\",2
+4,8
+";
+        assert_eq!(output, expected);
+
+        let config = Config {
+            line_terminator: LineTerminator::CrLf,
+            ..config()
+        };
+        let output = DelimiterFormatter::with(",").format(&results(), &config);
+        assert!(output.starts_with("Path,Lines\r\nplain.rs,2\r\n"));
+        assert!(output.ends_with("\r\n4,8\r\n"));
+    }
+
+    #[test]
+    fn test_footer_apart_from_data() {
+        let config = Config {
+            footer_in_data: false,
+            ..config()
+        };
+        let formatter = DelimiterFormatter::with(";");
+        let output = formatter.format(&results(), &config);
+        assert!(output.ends_with("newline.rs\";2\n"));
+        assert!(!output.contains("4;8"));
+        assert_eq!(formatter.totals(&results(), &config), "Path;Lines\n4;8\n");
+    }
 
-        cols.join(self.delimiter) + "\n"
-    }
-
-    fn build_footer(&self, results: &ScanResults, config: &Config) -> String {
-        self.selected_columns(
-            [
-                "Total".to_string(),
-                results.total.files.to_string(),
-                results.total.lines.to_string(),
-                results.total.words.to_string(),
-                results.total.chars.to_string(),
-                results.total.bytes.to_string(),
-            ],
-            config,
-        )
-        .join(self.delimiter)
+    #[test]
+    fn test_language_stats_rows() {
+        let results = scan_contents([("main.rs", "fn main() {}"), ("notes", "one\ntwo")]);
+        let config = Config {
+            columns: vec![Column::Language, Column::Lines],
+            footer: false,
+            stats: true,
+            ..Default::default()
+        };
+        let output = DelimiterFormatter::with(",").format(&results.group_by_language(), &config);
+        let labels = output
+            .lines()
+            .filter_map(|line| line.split_once(','))
+            .map(|(label, _)| label)
+            .filter(|label| label.ends_with("Median"))
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["Median", "Rust Median", "Text Median"]);
     }
 }
@@ -0,0 +1,502 @@
+use serde::Serialize;
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crate::{
+    helpers::{
+        ansi::repeat_to_width,
+        language::Language,
+        table::{Alignment, Table},
+    },
+    scanner::{File, ScanResults},
+};
+
+use super::{
+    ColorSupport, Config, Format, Formatter, Metric, Style, delimiter::record, html, openmetrics,
+    structured, svg, table::color,
+};
+
+/// Describes how the boundaries of the histogram buckets are spaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scale {
+    /// Buckets of equal width
+    Linear,
+    /// Buckets whose width doubles every step (`0`, `1`, `2-3`, `4-7`, ...)
+    Log,
+}
+
+impl std::str::FromStr for Scale {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "lin" | "linear" => Ok(Self::Linear),
+            "log" | "logarithmic" => Ok(Self::Log),
+            x => Err(format!("Invalid histogram scale: {x}")),
+        }
+    }
+}
+
+/// The number of files of a particular language in a [`Bucket`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageCount {
+    pub language: Language,
+    pub count: usize,
+}
+
+/// A single histogram bucket covering the half-open range `lower..upper`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bucket {
+    /// The inclusive lower boundary of the bucket
+    pub lower: u64,
+    /// The exclusive upper boundary of the bucket
+    pub upper: u64,
+    /// The number of files in the bucket
+    pub count: usize,
+    /// The number of files in the bucket per language, in descending order of count
+    pub languages: Vec<LanguageCount>,
+}
+
+impl Bucket {
+    /// A human-readable label for the (inclusive) range of values in the bucket
+    pub(super) fn label(&self) -> String {
+        if self.upper - self.lower <= 1 {
+            self.lower.to_string()
+        } else {
+            format!("{}-{}", self.lower, self.upper - 1)
+        }
+    }
+}
+
+/// Represents the distribution of the files in [`ScanResults`] over a [`Metric`]
+#[derive(Debug, Serialize)]
+pub struct Histogram {
+    pub metric: Metric,
+    pub scale: Scale,
+    pub buckets: Vec<Bucket>,
+}
+
+impl Histogram {
+    /// Buckets the files by the given [`Metric`]. For a [linear][Scale::Linear] scale, the range of values
+    /// is split into (at most) `bins` buckets of equal width. For a [logarithmic][Scale::Log] scale, the
+    /// number of buckets is determined by the largest value instead.
+    pub fn new(files: &[File], metric: Metric, scale: Scale, bins: usize) -> Histogram {
+        let values = files
+            .iter()
+            .map(|file| (file.value(metric).unwrap_or_default().round() as u64, file))
+            .collect::<Vec<_>>();
+
+        let max = match values.iter().map(|(v, _)| *v).max() {
+            Some(max) => max,
+            None => {
+                return Histogram {
+                    metric,
+                    scale,
+                    buckets: Vec::new(),
+                };
+            }
+        };
+
+        // Determine the boundaries of the buckets and a function that maps a value to its bucket
+        let width = max / bins.max(1) as u64 + 1;
+        let index = |value: u64| -> usize {
+            match scale {
+                Scale::Linear => (value / width) as usize,
+                Scale::Log if value == 0 => 0,
+                Scale::Log => 1 + value.ilog2() as usize,
+            }
+        };
+        let bounds = |i: usize| -> (u64, u64) {
+            match scale {
+                Scale::Linear => (i as u64 * width, (i as u64 + 1) * width),
+                Scale::Log if i == 0 => (0, 1),
+                Scale::Log => (1 << (i - 1), 1 << i),
+            }
+        };
+
+        // Count the files (per language) in each bucket
+        let mut counts = vec![HashMap::<&Language, usize>::new(); index(max) + 1];
+        for (value, file) in &values {
+            *counts[index(*value)].entry(&file.language).or_default() += file.count;
+        }
+
+        let buckets = counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, languages)| {
+                let (lower, upper) = bounds(i);
+                Bucket {
+                    lower,
+                    upper,
+                    count: languages.values().sum(),
+                    languages: sorted_counts(languages),
+                }
+            })
+            .collect();
+
+        Histogram {
+            metric,
+            scale,
+            buckets,
+        }
+    }
+
+    /// The languages across all buckets, in descending order of the number of files
+    fn languages(&self) -> Vec<Language> {
+        let mut counts = HashMap::new();
+        for bucket in &self.buckets {
+            for lc in &bucket.languages {
+                *counts.entry(&lc.language).or_default() += lc.count;
+            }
+        }
+        sorted_counts(counts)
+            .into_iter()
+            .map(|lc| lc.language)
+            .collect()
+    }
+}
+
+/// Sorts the language counts in descending order of count (and then by name, so that the order is deterministic)
+fn sorted_counts(counts: HashMap<&Language, usize>) -> Vec<LanguageCount> {
+    let mut counts = counts
+        .into_iter()
+        .map(|(language, count)| LanguageCount {
+            language: language.clone(),
+            count,
+        })
+        .collect::<Vec<_>>();
+    counts.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.language.to_string().cmp(&b.language.to_string()))
+    });
+    counts
+}
+
+/// Renders the [`Histogram`] of the files in [`ScanResults`] as a horizontal bar chart (or as data for machine formats)
+///
+/// The code-scanning formats ([SARIF][Format::SARIF] and [checkstyle][Format::Checkstyle]) are reports of the
+/// [check mode][Config::check], so a histogram can't be written in them.
+#[derive(Debug, Default)]
+pub struct HistogramFormatter {}
+
+impl Formatter for HistogramFormatter {
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let histogram = Histogram::new(
+            &results.files,
+            config.histogram.unwrap_or(Metric::Lines),
+            config.histogram_scale,
+            config.histogram_bins,
+        );
+
+        let output = match config.format {
+            Format::JSON => {
+                return serde_json::to_writer_pretty(writer, &histogram).map_err(io::Error::from);
+            }
+            Format::NDJSON => {
+                for bucket in &histogram.buckets {
+                    serde_json::to_writer(&mut *writer, bucket)?;
+                    writer.write_all(b"\n")?;
+                }
+                return Ok(());
+            }
+            Format::YAML => serde_norway::to_string(&histogram).map_err(io::Error::other)?,
+            Format::TOML => structured::to_toml(serde_json::to_value(&histogram)?)?,
+            Format::XML => structured::to_xml(
+                "histogram",
+                &serde_json::to_value(&histogram).unwrap_or_default(),
+            ),
+            Format::CSV | Format::TSV => self.delimited(&histogram, config),
+            Format::Table | Format::Plain => self.chart(&histogram, results, config),
+            Format::SARIF | Format::Checkstyle => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "a histogram can't be written as a code-scanning report",
+                ));
+            }
+            Format::SVG => svg::histogram(&histogram, config),
+            Format::OpenMetrics => openmetrics::histogram(&histogram, results, config),
+            Format::HTML => {
+                let config = Config {
+                    color_support: ColorSupport::None,
+                    ..config.clone()
+                };
+                let chart = self.chart(&histogram, results, &config);
+                html::page(
+                    "tali histogram",
+                    &format!("<pre>{}</pre>\n", html::escape(&chart)),
+                )
+            }
+        };
+        writer.write_all(output.as_bytes())
+    }
+}
+
+impl HistogramFormatter {
+    /// Renders the histogram as a bar chart table
+    fn chart(&self, histogram: &Histogram, results: &ScanResults, config: &Config) -> String {
+        let max_count = histogram.buckets.iter().map(|b| b.count).max();
+        let mut table = Table::default();
+        table
+            .with_style(config.table_style)
+            .with_border_escape(config.theme.separator.escape(config.color_support));
+        let paint = |style: &Style, text: &str| style.paint(text, config.color_support);
+
+        for bucket in &histogram.buckets {
+            table.add_row(vec![
+                bucket.label(),
+                paint(&config.theme.numbers, &bucket.count.to_string()),
+                self.build_bar(bucket, max_count.unwrap_or_default(), config),
+            ]);
+        }
+
+        if config.header {
+            let header = [&capitalize(&histogram.metric.to_string()), "Files", "Graph"];
+            table.with_header(
+                header
+                    .iter()
+                    .map(|title| paint(&config.theme.header, title))
+                    .collect(),
+            );
+        }
+
+        if config.footer {
+            table.with_footer(vec![
+                paint(&config.theme.footer, "Total"),
+                paint(&config.theme.footer, &results.total.files.to_string()),
+            ]);
+        }
+
+        if config.alignment {
+            table.with_alignments(vec![Alignment::Right, Alignment::Right, Alignment::Left]);
+        }
+
+        let mut res = table.display();
+
+        // Show a legend for the language colours of the stacked bars
+        if config.histogram_stacked && config.use_colors() {
+            let legend = histogram
+                .languages()
+                .into_iter()
+                .map(|lang| color(&lang, &format!("{} {}", config.graph_fill, lang), config))
+                .collect::<Vec<_>>();
+            res.push_str(&legend.join("  "));
+        }
+
+        res
+    }
+
+    /// Builds the bar for a bucket, scaled relative to the bucket with the most files
+    fn build_bar(&self, bucket: &Bucket, max_count: usize, config: &Config) -> String {
+        let size = config.graph_size;
+        let scale = |count: usize| -> usize {
+            if max_count == 0 {
+                0
+            } else {
+                (count as f64 / max_count as f64 * size as f64).round() as usize
+            }
+        };
+        let length = scale(bucket.count);
+
+        let bar = if config.histogram_stacked && config.use_colors() {
+            // Each language gets a segment proportional to its share of the bucket. The segment boundaries
+            // are rounded cumulatively so that the segments always add up to the length of the whole bar.
+            let mut bar = String::new();
+            let (mut cumulative, mut drawn) = (0, 0);
+            for lc in &bucket.languages {
+                cumulative += lc.count;
+                let end = scale(cumulative);
+                let segment = repeat_to_width(&config.graph_fill, end.saturating_sub(drawn));
+                bar.push_str(&config.theme.bar(
+                    lc.language.color(),
+                    &segment,
+                    config.color_support,
+                ));
+                drawn = end;
+            }
+            bar
+        } else {
+            let bar = repeat_to_width(&config.graph_fill, length);
+            config.theme.graph.paint(&bar, config.color_support)
+        };
+
+        bar + &repeat_to_width(&config.graph_blank, size.saturating_sub(length))
+    }
+
+    /// Renders the histogram as delimiter-separated values with the bucket boundaries and counts.
+    /// The stacked variant has an additional column for each language.
+    fn delimited(&self, histogram: &Histogram, config: &Config) -> String {
+        let delimiter = config.field_separator();
+        let languages = if config.histogram_stacked {
+            histogram.languages()
+        } else {
+            Vec::new()
+        };
+        let mut res = String::new();
+
+        if config.header {
+            let mut header = vec!["Lower".to_string(), "Upper".into(), "Files".into()];
+            header.extend(languages.iter().map(|lang| lang.to_string()));
+            res.push_str(&record(header, delimiter, config));
+        }
+
+        for bucket in &histogram.buckets {
+            let mut row = vec![
+                bucket.lower.to_string(),
+                bucket.upper.to_string(),
+                bucket.count.to_string(),
+            ];
+            row.extend(languages.iter().map(|lang| {
+                let count = bucket.languages.iter().find(|lc| &lc.language == lang);
+                count.map(|lc| lc.count).unwrap_or_default().to_string()
+            }));
+            res.push_str(&record(row, delimiter, config));
+        }
+
+        if config.footer {
+            let total: usize = histogram.buckets.iter().map(|b| b.count).sum();
+            res.push_str(&record(
+                ["Total", "", &total.to_string()],
+                delimiter,
+                config,
+            ));
+        }
+
+        res
+    }
+}
+
+/// Capitalizes the first letter of the string
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(lines: usize, language: Language) -> File {
+        let mut file = File::scan_reader(std::io::Cursor::new("")).unwrap();
+        file.lines = lines;
+        file.language = language;
+        file
+    }
+
+    #[test]
+    fn test_linear_buckets() {
+        let files = vec![
+            file(0, Language::Rust),
+            file(5, Language::Rust),
+            file(12, Language::Markdown),
+            file(19, Language::Rust),
+        ];
+        let histogram = Histogram::new(&files, Metric::Lines, Scale::Linear, 2);
+
+        let bounds: Vec<_> = histogram
+            .buckets
+            .iter()
+            .map(|b| (b.lower, b.upper, b.count))
+            .collect();
+        assert_eq!(bounds, vec![(0, 10, 2), (10, 20, 2)]);
+        assert_eq!(
+            histogram.buckets[1].languages,
+            vec![
+                LanguageCount {
+                    language: Language::Markdown,
+                    count: 1
+                },
+                LanguageCount {
+                    language: Language::Rust,
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_buckets() {
+        let files = vec![
+            file(0, Language::Rust),
+            file(1, Language::Rust),
+            file(3, Language::Rust),
+            file(9, Language::Rust),
+        ];
+        let histogram = Histogram::new(&files, Metric::Lines, Scale::Log, 10);
+
+        let bounds: Vec<_> = histogram
+            .buckets
+            .iter()
+            .map(|b| (b.lower, b.upper, b.count))
+            .collect();
+        assert_eq!(
+            bounds,
+            vec![(0, 1, 1), (1, 2, 1), (2, 4, 1), (4, 8, 0), (8, 16, 1)]
+        );
+    }
+
+    #[test]
+    fn test_empty_histogram() {
+        let histogram = Histogram::new(&[], Metric::Bytes, Scale::Linear, 10);
+        assert!(histogram.buckets.is_empty());
+    }
+
+    #[test]
+    fn test_stacked_bar_segments_add_up() {
+        let bucket = Bucket {
+            lower: 0,
+            upper: 10,
+            count: 3,
+            languages: vec![
+                LanguageCount {
+                    language: Language::Rust,
+                    count: 2,
+                },
+                LanguageCount {
+                    language: Language::Markdown,
+                    count: 1,
+                },
+            ],
+        };
+        let config = Config {
+            histogram_stacked: true,
+            graph_fill: "#".into(),
+            graph_blank: ".".into(),
+            graph_size: 10,
+            ..Default::default()
+        };
+
+        let bar = HistogramFormatter::default().build_bar(&bucket, 6, &config);
+
+        assert_eq!(crate::helpers::ansi::visible_width(&bar), 10);
+        assert_eq!(bar.matches('#').count(), 5);
+    }
+
+    #[test]
+    fn test_delimited_output() {
+        let files = vec![file(1, Language::Rust), file(12, Language::Markdown)];
+        let histogram = Histogram::new(&files, Metric::Lines, Scale::Linear, 2);
+        let config = Config {
+            histogram_stacked: true,
+            ..Default::default()
+        };
+
+        let output = HistogramFormatter::default().delimited(&histogram, &config);
+
+        assert_eq!(
+            output,
+            "Lower,Upper,Files,Markdown,Rust\n0,7,1,0,1\n7,14,1,1,0\nTotal,,2\n"
+        );
+    }
+}
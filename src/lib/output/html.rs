@@ -0,0 +1,435 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use crate::{
+    helpers::{path, table::Alignment},
+    scanner::{File, ScanResults},
+};
+
+use super::{Column, Config, Formatter, Metric, table::graph_fraction};
+
+/// Renders the results as a standalone HTML report. Everything (styles and scripts) is inlined,
+/// so the report can be opened offline or attached as a CI artifact.
+#[derive(Default, Debug)]
+pub struct HTMLFormatter {}
+
+impl Formatter for HTMLFormatter {
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let mut body = String::new();
+        body.push_str(&self.totals(results, config));
+        body.push_str(&self.languages(results, config));
+        if !config.group_by_language {
+            body.push_str(&self.treemap(results, config));
+        }
+        body.push_str(&self.files(results, config));
+        writer.write_all(page("tali report", &body).as_bytes())
+    }
+}
+
+impl HTMLFormatter {
+    /// The summary cards with the total of each selected metric
+    fn totals(&self, results: &ScanResults, config: &Config) -> String {
+        let mut cards = vec![(Column::Path.total(results, config), "Files".to_string())];
+        for column in config.data_columns() {
+            if let Some(metric) = column.metric().filter(Metric::is_additive) {
+                let column = Column::from(metric);
+                cards.push((column.total(results, config), column.header(config)));
+            }
+        }
+
+        let cards = cards
+            .iter()
+            .map(|(value, label)| {
+                format!(
+                    "<div class=\"card\"><strong>{}</strong><span>{}</span></div>",
+                    escape(value),
+                    escape(label)
+                )
+            })
+            .collect::<String>();
+        format!("<section class=\"totals\">{cards}</section>\n")
+    }
+
+    /// The breakdown of the files, lines, bytes and the graph metric by language
+    fn languages(&self, results: &ScanResults, config: &Config) -> String {
+        // The rows are already languages when grouped by language
+        let grouped;
+        let results = if config.group_by_language {
+            results
+        } else {
+            grouped = results.group_by_language();
+            &grouped
+        };
+        let share = |file: &File| file.value(config.graph_by).unwrap_or_default();
+        let mut languages = results.files.iter().collect::<Vec<_>>();
+        languages.sort_by(|a, b| share(b).total_cmp(&share(a)));
+        let total = languages.iter().map(|file| share(file)).sum::<f64>();
+
+        let by = Column::from(config.graph_by).header(config);
+        let mut html = format!(
+            "<section>\n<h2>Languages</h2>\n<table class=\"sortable\">\n<thead><tr>\
+            <th>Language</th><th class=\"num\">Files</th><th class=\"num\">Lines</th>\
+            <th class=\"num\">Bytes</th><th class=\"num\">{} %</th></tr></thead>\n<tbody>\n",
+            escape(&by)
+        );
+        for file in languages {
+            let percent = if total > 0.0 {
+                share(file) / total * 100.0
+            } else {
+                0.0
+            };
+            html.push_str(&format!(
+                "<tr><td data-sort=\"{name}\"><span class=\"swatch\" style=\"background:{color}\"></span>{name}</td>\
+                <td class=\"num\">{files}</td><td class=\"num\">{lines}</td>\
+                <td class=\"num\" data-sort=\"{bytes}\">{bytes_display}</td>\
+                <td class=\"num\" data-sort=\"{percent}\">{percent:.*}%</td></tr>\n",
+                config.percent_precision,
+                name = escape(&file.language.to_string()),
+                color = hex(file.language.color()),
+                files = file.count,
+                lines = file.lines,
+                bytes = file.bytes,
+                bytes_display = Column::Bytes.value(file, results, config),
+            ));
+        }
+        html.push_str("</tbody>\n</table>\n</section>\n");
+        html
+    }
+
+    /// A treemap of the directories, sized by the graph metric. The nested boxes alternate between
+    /// rows and columns (a slice-and-dice layout) and are laid out by the browser with flexbox.
+    fn treemap(&self, results: &ScanResults, config: &Config) -> String {
+        let mut root = Directory::default();
+        for file in &results.files {
+            root.insert(file, file.value(config.graph_by).unwrap_or_default());
+        }
+        if root.value <= 0.0 || root.value.is_nan() {
+            return String::new();
+        }
+
+        let by = Column::from(config.graph_by).header(config);
+        let mut html = format!(
+            "<section>\n<h2>Directories</h2>\n<p class=\"note\">Sized by {}</p>\n<div class=\"treemap\">",
+            escape(&by.to_lowercase())
+        );
+        root.render(".", 0, &mut html);
+        html.push_str("</div>\n</section>\n");
+        html
+    }
+
+    /// The sortable table of the files, with the selected columns
+    fn files(&self, results: &ScanResults, config: &Config) -> String {
+        let columns = &config.columns;
+        let heading = if config.group_by_language {
+            "Languages"
+        } else {
+            "Files"
+        };
+        let mut html =
+            format!("<section>\n<h2>{heading}</h2>\n<table class=\"sortable\">\n<thead><tr>");
+        for column in columns {
+            html.push_str(&format!(
+                "<th{}>{}</th>",
+                class(*column, config),
+                escape(&column.header(config))
+            ));
+        }
+        html.push_str("</tr></thead>\n<tbody>\n");
+
+        for file in &results.files {
+            html.push_str("<tr>");
+            for column in columns {
+                html.push_str(&self.cell(*column, file, results, config));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</tbody>\n");
+
+        if config.footer {
+            html.push_str("<tfoot>\n");
+            if let Some(others) = &results.others {
+                let cells = columns
+                    .iter()
+                    .map(|column| column.others(others, results, config))
+                    .collect::<Vec<_>>();
+                html.push_str(&footer_row(&cells, columns, config));
+            }
+            let cells = columns
+                .iter()
+                .map(|column| column.total(results, config))
+                .collect::<Vec<_>>();
+            html.push_str(&footer_row(&cells, columns, config));
+            html.push_str("</tfoot>\n");
+        }
+
+        html.push_str("</table>\n</section>\n");
+        html
+    }
+
+    /// A cell of the files table. Numeric cells carry their raw value, so that they sort numerically.
+    fn cell(&self, column: Column, file: &File, results: &ScanResults, config: &Config) -> String {
+        let class = class(column, config);
+        match column {
+            Column::Graph => {
+                let fraction = graph_fraction(file, results, config);
+                let fraction = if fraction.is_finite() {
+                    fraction.clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                format!(
+                    "<td data-sort=\"{fraction}\"><div class=\"bar\" style=\"width:{:.1}%;background:{}\"></div></td>",
+                    fraction * 100.0,
+                    hex(file.language.color())
+                )
+            }
+            Column::Language => format!(
+                "<td{class}><span class=\"swatch\" style=\"background:{}\"></span>{}</td>",
+                hex(file.language.color()),
+                escape(&column.value(file, results, config))
+            ),
+            column => {
+                let raw = match column {
+                    Column::Path if config.group_by_language => Some(file.count as f64),
+                    Column::Percent(_) => column.percentage(file, results),
+                    column => column.metric().and_then(|metric| file.value(metric)),
+                };
+                let sort = raw
+                    .map(|value| format!(" data-sort=\"{value}\""))
+                    .unwrap_or_default();
+                format!(
+                    "<td{class}{sort}>{}</td>",
+                    escape(&column.value(file, results, config))
+                )
+            }
+        }
+    }
+}
+
+/// A directory in the [treemap][HTMLFormatter::treemap], with the total value of the files it contains
+#[derive(Default, Debug)]
+struct Directory<'a> {
+    value: f64,
+    directories: BTreeMap<String, Directory<'a>>,
+    files: Vec<(&'a File, f64)>,
+}
+
+impl<'a> Directory<'a> {
+    /// Adds the file (with the given value) under its parent directories
+    fn insert(&mut self, file: &'a File, value: f64) {
+        let mut directory = self;
+        directory.value += value;
+        let parent = file.path.parent().map(path::display).unwrap_or_default();
+        for name in parent.split(['/', '\\']).filter(|name| !name.is_empty()) {
+            directory = directory.directories.entry(name.to_string()).or_default();
+            directory.value += value;
+        }
+        directory.files.push((file, value));
+    }
+
+    /// Renders the directory as a box holding its subdirectories and files, largest first.
+    /// The boxes at even depths are laid out in rows, and those at odd depths in columns.
+    fn render(&self, name: &str, depth: usize, html: &mut String) {
+        let direction = if depth.is_multiple_of(2) {
+            "row"
+        } else {
+            "column"
+        };
+        html.push_str(&format!(
+            "<div class=\"dir\" style=\"flex:{} 1 0;flex-direction:{direction}\" title=\"{} ({})\">",
+            self.value,
+            escape(name),
+            self.value
+        ));
+
+        let mut directories = self
+            .directories
+            .iter()
+            .filter(|(_, directory)| directory.value > 0.0)
+            .collect::<Vec<_>>();
+        directories.sort_by(|a, b| b.1.value.total_cmp(&a.1.value));
+        for (name, directory) in directories {
+            directory.render(name, depth + 1, html);
+        }
+
+        let mut files = self
+            .files
+            .iter()
+            .filter(|(_, value)| *value > 0.0)
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| b.1.total_cmp(&a.1));
+        for (file, value) in files {
+            html.push_str(&format!(
+                "<div class=\"file\" style=\"flex:{value} 1 0;background:{}\" title=\"{} ({value})\"></div>",
+                hex(file.language.color()),
+                escape(&path::display(&file.path))
+            ));
+        }
+        html.push_str("</div>");
+    }
+}
+
+/// A row in the footer of the files table
+fn footer_row(cells: &[String], columns: &[Column], config: &Config) -> String {
+    let cells = cells
+        .iter()
+        .zip(columns)
+        .map(|(cell, column)| format!("<td{}>{}</td>", class(*column, config), escape(cell)))
+        .collect::<String>();
+    format!("<tr>{cells}</tr>\n")
+}
+
+/// The class attribute of the column's cells, which right-aligns the numbers
+fn class(column: Column, config: &Config) -> &'static str {
+    match column.alignment(config) {
+        Alignment::Right => " class=\"num\"",
+        _ => "",
+    }
+}
+
+/// Formats an RGB color as a CSS hex color
+pub(super) fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Escapes the characters that have a special meaning in HTML text and attributes
+pub(super) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Wraps the body in a complete HTML document, with the inline styles and the table-sorting script
+pub(super) fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+        <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n\
+        {body}<script>{SCRIPT}</script>\n</body>\n</html>\n",
+        title = escape(title),
+    )
+}
+
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 2rem; color: #222; background: #fafafa; }
+h1 { font-size: 1.5rem; }
+h2 { font-size: 1.2rem; margin-top: 2rem; }
+table { border-collapse: collapse; background: #fff; }
+th, td { padding: 0.25rem 0.75rem; border-bottom: 1px solid #e4e4e4; text-align: left; white-space: nowrap; }
+th { cursor: pointer; user-select: none; background: #f0f0f0; }
+th.asc::after { content: " ▲"; }
+th.desc::after { content: " ▼"; }
+tfoot td { font-weight: bold; border-top: 2px solid #ccc; }
+.num { text-align: right; font-variant-numeric: tabular-nums; }
+.swatch { display: inline-block; width: 0.8em; height: 0.8em; margin-right: 0.4em; border-radius: 2px; vertical-align: middle; }
+.bar { height: 0.8em; min-width: 1px; border-radius: 2px; }
+td:has(.bar) { width: 12rem; }
+.totals { display: flex; flex-wrap: wrap; gap: 1rem; }
+.card { display: flex; flex-direction: column; padding: 0.75rem 1rem; background: #fff; border: 1px solid #e4e4e4; border-radius: 4px; }
+.card strong { font-size: 1.3rem; }
+.card span { color: #666; }
+.note { color: #666; }
+.treemap { display: flex; height: 24rem; background: #fff; border: 1px solid #ccc; }
+.dir { display: flex; min-width: 0; min-height: 0; box-sizing: border-box; border: 1px solid #fff; }
+.file { min-width: 0; min-height: 0; box-sizing: border-box; border: 1px solid rgba(255, 255, 255, 0.6); }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable").forEach((table) => {
+  table.querySelectorAll("thead th").forEach((th, index) => {
+    th.addEventListener("click", () => {
+      const ascending = !th.classList.contains("asc");
+      table.querySelectorAll("thead th").forEach((other) => other.classList.remove("asc", "desc"));
+      th.classList.add(ascending ? "asc" : "desc");
+      const key = (row) => {
+        const cell = row.cells[index];
+        return cell.dataset.sort ?? cell.textContent;
+      };
+      const tbody = table.tBodies[0];
+      const rows = Array.from(tbody.rows).sort((a, b) => {
+        const [x, y] = [key(a), key(b)];
+        const order = isNaN(x) || isNaN(y) ? x.localeCompare(y, undefined, { numeric: true }) : x - y;
+        return ascending ? order : -order;
+      });
+      rows.forEach((row) => tbody.appendChild(row));
+    });
+  });
+});
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::language::Language;
+    use crate::output::scan_contents;
+
+    fn results() -> ScanResults {
+        scan_contents([
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/lib/<mod>.rs", "one\ntwo\n"),
+            ("README.md", "# tali"),
+        ])
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"<a href="x">&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_html_report() {
+        let config = Config::default();
+        let output = HTMLFormatter::default().format(&results(), &config);
+
+        assert!(output.starts_with("<!DOCTYPE html>"));
+        assert!(output.trim_end().ends_with("</html>"));
+        // Self-contained: no external stylesheets, scripts or images
+        assert!(!output.contains("http"));
+        assert!(!output.contains("src=\""));
+
+        assert!(output.contains("<h2>Languages</h2>"));
+        assert!(output.contains(&hex(Language::Rust.color())));
+        assert!(output.contains("src/lib/&lt;mod&gt;.rs"));
+        assert!(!output.contains("<mod>"));
+        assert!(output.contains("<td class=\"num\" data-sort=\"2\">2</td>"));
+        assert!(output.contains("<tfoot>"));
+    }
+
+    #[test]
+    fn test_treemap_nests_directories() {
+        let mut root = Directory::default();
+        let results = results();
+        for file in &results.files {
+            root.insert(file, file.lines as f64);
+        }
+        assert_eq!(root.value, 4.0);
+        assert_eq!(root.files.len(), 1);
+        assert_eq!(root.directories["src"].value, 3.0);
+        assert_eq!(root.directories["src"].directories["lib"].value, 2.0);
+
+        let mut html = String::new();
+        root.render(".", 0, &mut html);
+        assert_eq!(html.matches("class=\"dir\"").count(), 3);
+        assert_eq!(html.matches("class=\"file\"").count(), 3);
+        assert!(html.contains("flex-direction:column"));
+    }
+}
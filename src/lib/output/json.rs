@@ -1,136 +1,325 @@
-use super::{Config, Formatter};
+use std::io::{self, Write};
+
+use serde_json::{Map, Value};
+
+use crate::scanner::{File, ScanResults};
+
+use super::{Column, Config, Formatter, Metric, RowFormatter};
 
 #[derive(Default, Debug)]
 pub struct JSONFormatter {}
 
 impl Formatter for JSONFormatter {
-    fn format(&self, results: &crate::scanner::ScanResults, _config: &Config) -> String {
-        serde_json::to_string_pretty(&results).unwrap_or_default()
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        serde_json::to_writer_pretty(writer, &self.jsonify(results, config))?;
+        Ok(())
+    }
+}
+
+/// Renders the files as newline-delimited JSON: an object per line for each file, with the same fields as in
+/// the [JSON output][JSONFormatter]. Only the files are listed, not the aggregates (the "Others" row and the totals).
+#[derive(Default, Debug)]
+pub struct NDJSONFormatter {}
+
+impl Formatter for NDJSONFormatter {
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        for file in &results.files {
+            self.write_row(file, results, config, writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl RowFormatter for NDJSONFormatter {
+    fn write_row(
+        &self,
+        file: &File,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let json = JSONFormatter::default().jsonify_file(file, results, config);
+        serde_json::to_writer(&mut *writer, &json)?;
+        writer.write_all(b"\n")
     }
 }
 
-// The following helper methods construct json object based on the values allowed by the config
-//
-// impl JSONFormatter {
-//     fn jsonify_files(
-//         &self,
-//         results: &crate::scanner::ScanResults,
-//         config: &Display,
-//     ) -> serde_json::Value {
-//         let json_files: Vec<serde_json::Value> = results
-//             .files
-//             .iter()
-//             .map(|file| {
-//                 let mut map = serde_json::Map::new();
-
-//                 map.insert(
-//                     "path".into(),
-//                     serde_json::Value::String(file.path.to_string_lossy().to_string()),
-//                 );
-
-//                 if config.language {
-//                     map.insert(
-//                         "language".into(),
-//                         serde_json::Value::String(file.language.to_string()),
-//                     );
-//                 }
-
-//                 if config.lines {
-//                     map.insert("lines".into(), serde_json::Value::Number(file.lines.into()));
-//                 }
-
-//                 if config.words {
-//                     map.insert("words".into(), serde_json::Value::Number(file.words.into()));
-//                 }
-
-//                 if config.chars {
-//                     map.insert("chars".into(), serde_json::Value::Number(file.chars.into()));
-//                 }
-
-//                 if config.bytes {
-//                     map.insert("bytes".into(), serde_json::Value::Number(file.bytes.into()));
-//                 }
-
-//                 serde_json::Value::Object(map)
-//             })
-//             .collect();
-
-//         serde_json::Value::Array(json_files)
-//     }
-
-//     fn jsonify_total(
-//         &self,
-//         results: &crate::scanner::ScanResults,
-//         config: &Display,
-//     ) -> serde_json::Value {
-//         let mut json_total = serde_json::Map::new();
-
-//         if config.lines {
-//             json_total.insert(
-//                 "lines".into(),
-//                 serde_json::Value::Number(results.total.lines.into()),
-//             );
-//         }
-
-//         if config.words {
-//             json_total.insert(
-//                 "words".into(),
-//                 serde_json::Value::Number(results.total.words.into()),
-//             );
-//         }
-
-//         if config.chars {
-//             json_total.insert(
-//                 "chars".into(),
-//                 serde_json::Value::Number(results.total.chars.into()),
-//             );
-//         }
-
-//         if config.bytes {
-//             json_total.insert(
-//                 "bytes".into(),
-//                 serde_json::Value::Number(results.total.bytes.into()),
-//             );
-//         }
-
-//         serde_json::Value::Object(json_total)
-//     }
-
-//     fn jsonify_max(
-//         &self,
-//         results: &crate::scanner::ScanResults,
-//         config: &Display,
-//     ) -> serde_json::Value {
-//         let mut json_max = serde_json::Map::new();
-
-//         if config.lines {
-//             json_max.insert(
-//                 "lines".into(),
-//                 serde_json::Value::Number(results.max.lines.into()),
-//             );
-//         }
-
-//         if config.words {
-//             json_max.insert(
-//                 "words".into(),
-//                 serde_json::Value::Number(results.max.words.into()),
-//             );
-//         }
-
-//         if config.chars {
-//             json_max.insert(
-//                 "chars".into(),
-//                 serde_json::Value::Number(results.max.chars.into()),
-//             );
-//         }
-
-//         if config.bytes {
-//             json_max.insert(
-//                 "bytes".into(),
-//                 serde_json::Value::Number(results.max.bytes.into()),
-//             );
-//         }
-
-//         serde_json::Value::Object(json_max)
-//     }
-// }
+// The following helper methods construct json objects based on the columns selected in the config.
+// Each file entry always includes its identity (the path, or the language when grouped by language).
+// Unless the columns were explicitly selected, every field of the results is included instead.
+impl JSONFormatter {
+    /// The structure of the output: the files, the "Others" row, the total, the max and the statistics.
+    /// The other structured formats (like YAML) serialize the same structure.
+    pub(super) fn jsonify(&self, results: &ScanResults, config: &Config) -> Value {
+        if config.all_fields {
+            return self.jsonify_all(results, config);
+        }
+
+        let mut json = Map::new();
+
+        json.insert("files".into(), self.jsonify_files(results, config));
+        if let Some(others) = &results.others {
+            json.insert(
+                "others".into(),
+                self.jsonify_others(others, results, config),
+            );
+        }
+        json.insert("total".into(), self.jsonify_total(results, config));
+        json.insert("max".into(), self.jsonify_max(results, config));
+        json.insert("stats".into(), self.jsonify_stats(&results.stats, config));
+        if !results.language_stats.is_empty() {
+            let language_stats = results
+                .language_stats
+                .iter()
+                .map(|(lang, stats)| (lang.clone(), self.jsonify_stats(stats, config)))
+                .collect();
+            json.insert("language_stats".into(), Value::Object(language_stats));
+        }
+
+        Value::Object(json)
+    }
+
+    /// The serialized [`ScanResults`], with the selected percentages and human-readable numbers in the files
+    fn jsonify_all(&self, results: &ScanResults, config: &Config) -> Value {
+        let mut json = serde_json::to_value(results).unwrap_or_default();
+        json["files"] = self.jsonify_files(results, config);
+        if let Some(others) = &results.others {
+            json["others"] = self.jsonify_others(others, results, config);
+        }
+        json
+    }
+
+    fn jsonify_files(&self, results: &ScanResults, config: &Config) -> Value {
+        let files = results
+            .files
+            .iter()
+            .map(|file| self.jsonify_file(file, results, config))
+            .collect();
+        Value::Array(files)
+    }
+
+    /// The "Others" row, labelled like in the [table][Column::others] rather than with the aggregate's language
+    fn jsonify_others(&self, others: &File, results: &ScanResults, config: &Config) -> Value {
+        let mut json = self.jsonify_file(others, results, config);
+        if let Some(language) = json.get_mut(Column::Language.key(config)) {
+            *language = Column::Language.others(others, results, config).into();
+        }
+        json
+    }
+
+    pub(super) fn jsonify_file(
+        &self,
+        file: &File,
+        results: &ScanResults,
+        config: &Config,
+    ) -> Value {
+        let identity = if config.group_by_language {
+            Column::Language
+        } else {
+            Column::Path
+        };
+
+        if config.all_fields {
+            return self.jsonify_all_fields(file, results, config);
+        }
+
+        let mut map = Map::new();
+        if !config.has(identity) {
+            map.insert(
+                identity.key(config),
+                self.jsonify_value(identity, file, results, config),
+            );
+        }
+        for column in config.data_columns() {
+            map.insert(
+                column.key(config),
+                self.jsonify_value(*column, file, results, config),
+            );
+        }
+        Value::Object(map)
+    }
+
+    /// Every field of the file, followed by the selected percentages (and the human-readable numbers, if enabled)
+    fn jsonify_all_fields(&self, file: &File, results: &ScanResults, config: &Config) -> Value {
+        let mut map = match serde_json::to_value(file) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        let columns = config.data_columns().filter(|column| match column {
+            Column::Percent(_) => true,
+            column => config.humanize() && column.metric().is_some(),
+        });
+        for column in columns {
+            map.insert(
+                column.key(config),
+                self.jsonify_value(*column, file, results, config),
+            );
+        }
+        Value::Object(map)
+    }
+
+    fn jsonify_value(
+        &self,
+        column: Column,
+        file: &File,
+        results: &ScanResults,
+        config: &Config,
+    ) -> Value {
+        match column {
+            // Human-readable numbers are strings, so they're only used when explicitly requested
+            column if config.humanize() => column.value(file, results, config).into(),
+            Column::Path if config.group_by_language => file.count.into(),
+            Column::AvgLineLength => file.avg_line_length.into(),
+            Column::Percent(_) => column.percentage(file, results).unwrap_or_default().into(),
+            column => match column.metric().and_then(|metric| file.value(metric)) {
+                Some(value) => (value as u64).into(),
+                None => column.value(file, results, config).into(),
+            },
+        }
+    }
+
+    fn jsonify_total(&self, results: &ScanResults, config: &Config) -> Value {
+        let mut map = Map::new();
+        map.insert("files".into(), results.total.files.into());
+        for metric in self.selected_metrics(config) {
+            if let Some(value) = results.total.value(metric) {
+                map.insert(metric.key(), (value as u64).into());
+            }
+        }
+        Value::Object(map)
+    }
+
+    fn jsonify_max(&self, results: &ScanResults, config: &Config) -> Value {
+        let mut map = Map::new();
+        for metric in self.selected_metrics(config) {
+            let value = results.max.value(metric).unwrap_or_default();
+            let value = match metric {
+                Metric::AvgLineLength => value.into(),
+                _ => (value as u64).into(),
+            };
+            map.insert(metric.key(), value);
+        }
+        Value::Object(map)
+    }
+
+    fn jsonify_stats(&self, stats: &crate::scanner::Stats, config: &Config) -> Value {
+        let mut map = Map::new();
+        for metric in self.selected_metrics(config) {
+            if let Some(summary) = stats.summary(metric) {
+                map.insert(
+                    metric.key(),
+                    serde_json::to_value(summary).unwrap_or_default(),
+                );
+            }
+        }
+        Value::Object(map)
+    }
+
+    /// The numeric metrics of the selected columns
+    fn selected_metrics<'a>(&self, config: &'a Config) -> impl Iterator<Item = Metric> + 'a {
+        config.columns.iter().filter_map(|column| column.metric())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::language::Language;
+    use crate::output::{Format, display};
+
+    #[test]
+    fn test_json_honours_selected_columns() {
+        let file = File::scan_reader(std::io::Cursor::new("one two\nthree")).unwrap();
+        let mut results = ScanResults::default();
+        results.total.add(&file);
+        results.max.track(&file);
+        results.files.push(file);
+        let config = Config {
+            columns: vec![Column::Lines, Column::Graph],
+            ..Default::default()
+        };
+
+        let output = JSONFormatter::default().format(&results, &config);
+        let json: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(
+            json["files"],
+            serde_json::json!([{ "path": "STDIN", "lines": 2 }])
+        );
+        assert_eq!(json["total"], serde_json::json!({ "files": 1, "lines": 2 }));
+        assert_eq!(json["max"], serde_json::json!({ "lines": 2 }));
+    }
+
+    #[test]
+    fn test_json_labels_the_grouped_others() {
+        let files = ["a.rs", "b.py", "c.md"].map(|path| {
+            let mut file = File::scan_reader(std::io::Cursor::new("one two\nthree")).unwrap();
+            file.path = path.into();
+            file.language = Language::from_path(path);
+            file
+        });
+        for all_fields in [false, true] {
+            let config = Config {
+                columns: vec![Column::Language, Column::Lines],
+                all_fields,
+                format: Format::JSON,
+                group_by_language: true,
+                top: Some(1),
+                ..Default::default()
+            };
+            let output = display(ScanResults::from_iter(files.clone()), config);
+            let json: Value = serde_json::from_str(&output).unwrap();
+            assert_eq!(json["others"]["language"], "Others");
+            assert_eq!(json["others"]["lines"], 4);
+        }
+    }
+
+    #[test]
+    fn test_json_serializes_every_field_unless_columns_are_selected() {
+        let file = File::scan_reader(std::io::Cursor::new("one two\nthree")).unwrap();
+        let results = ScanResults::from_iter([file]);
+        let config = Config {
+            columns: vec![Column::Lines, Column::Percent(Metric::Lines)],
+            all_fields: true,
+            ..Default::default()
+        };
+
+        let output = JSONFormatter::default().format(&results, &config);
+        let json: Value = serde_json::from_str(&output).unwrap();
+
+        let mut expected = serde_json::to_value(&results).unwrap();
+        expected["files"][0]["lines_percent"] = 100.0.into();
+        assert_eq!(json, expected);
+        assert_eq!(json["files"][0]["language"], "Text");
+        assert_eq!(json["files"][0]["count"], 1);
+        assert_eq!(json["total"]["words"], 3);
+    }
+
+    #[test]
+    fn test_ndjson_has_a_line_per_file() {
+        let mut results = ScanResults::default();
+        for path in ["a.rs", "b.rs"] {
+            let mut file = File::scan_reader(std::io::Cursor::new("one two\nthree")).unwrap();
+            file.path = path.into();
+            results.push(file);
+        }
+        results.filter(Some(1), |_| true);
+        let config = Config {
+            columns: vec![Column::Path, Column::Words],
+            ..Default::default()
+        };
+
+        let output = NDJSONFormatter::default().format(&results, &config);
+        assert_eq!(output, "{\"path\":\"a.rs\",\"words\":3}\n");
+    }
+}
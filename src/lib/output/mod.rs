@@ -1,24 +1,141 @@
-use crate::scanner::{ScanResults, SortOrder};
+use std::io::{self, Write};
 
+use crate::{
+    helpers::language::Language,
+    scanner::{File, ScanResults, SortKey, SortOrder, Stats, Summary},
+};
+
+pub use crate::helpers::{number::locale_separator, table::TableStyle};
+
+mod columns;
+pub use columns::Column;
 mod json;
-use json::*;
+pub use json::{JSONFormatter, NDJSONFormatter};
+mod structured;
+pub use structured::{TOMLFormatter, XMLFormatter, YAMLFormatter};
+mod template;
+pub use template::{Template, TemplateFormatter};
 mod table;
-use table::*;
+pub use table::TableFormatter;
 mod delimiter;
-use delimiter::*;
+pub use delimiter::DelimiterFormatter;
+mod html;
+pub use html::HTMLFormatter;
+mod svg;
+pub use svg::SVGFormatter;
+mod openmetrics;
+pub use openmetrics::{OpenMetricsFormatter, is_valid_metric_name};
+mod check;
+pub use check::{CheckFormatter, Rule, Violation, violations};
+mod histogram;
+pub use histogram::{Bucket, Histogram, HistogramFormatter, LanguageCount, Scale};
+mod theme;
+pub use theme::{Color, ColorSupport, Style, Theme, ThemeSettings};
+
+/// A [`Formatter`] that renders each file on its own row, so that the rows can be [streamed][stream]
+/// while the files are being scanned
+pub trait RowFormatter: Formatter {
+    /// Writes what comes before the rows (like the header)
+    fn write_header(&self, _config: &Config, _writer: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Writes the row of the file. The results hold the files before it.
+    fn write_row(
+        &self,
+        file: &File,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()>;
 
+    /// Writes what comes after the rows (like the totals), once every file has been scanned
+    fn write_footer(
+        &self,
+        _results: &ScanResults,
+        _config: &Config,
+        _writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Renders the [`ScanResults`] in an output format.
+///
+/// The formatters render the results as given, while [`display`] and [`write()`] prepare them for the
+/// [configuration][Config] first (grouping, sorting and filtering the files) and pick the formatter.
 pub trait Formatter {
-    fn format(&self, results: &ScanResults, config: &Config) -> String;
+    /// Renders the results into the writer, writing each part (like the rows of the delimiter-separated formats)
+    /// as soon as it is rendered
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()>;
+
+    /// Renders the results into a string
+    fn format(&self, results: &ScanResults, config: &Config) -> String {
+        let mut buffer = Vec::new();
+        // Writing into memory can't fail
+        self.write(results, config, &mut buffer).unwrap_or_default();
+        String::from_utf8_lossy(&buffer).into_owned()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Metric {
     Language,
+    Path,
     Files,
     Lines,
     Words,
     Chars,
+    Graphemes,
+    Width,
     Bytes,
+    MaxLineLength,
+    MaxLineWidth,
+    AvgLineLength,
+}
+
+impl Metric {
+    /// The numeric metrics, i.e. the ones that have a value for every [`File`]
+    pub const NUMERIC: [Metric; 9] = [
+        Metric::Lines,
+        Metric::Words,
+        Metric::Chars,
+        Metric::Graphemes,
+        Metric::Width,
+        Metric::Bytes,
+        Metric::MaxLineLength,
+        Metric::MaxLineWidth,
+        Metric::AvgLineLength,
+    ];
+
+    /// Whether the metric is numeric (as opposed to the language, path or file count)
+    pub fn is_numeric(&self) -> bool {
+        Self::NUMERIC.contains(self)
+    }
+
+    /// The key of the metric in the structured outputs, e.g. `max_line_length`
+    pub fn key(&self) -> String {
+        self.to_string().replace('-', "_")
+    }
+
+    /// Whether the metric can be summed across files (and so has a [total][crate::scanner::Totals])
+    pub fn is_additive(&self) -> bool {
+        matches!(
+            self,
+            Metric::Files
+                | Metric::Lines
+                | Metric::Words
+                | Metric::Chars
+                | Metric::Graphemes
+                | Metric::Width
+                | Metric::Bytes
+        )
+    }
 }
 
 impl std::str::FromStr for Metric {
@@ -26,37 +143,184 @@ impl std::str::FromStr for Metric {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "language" | "lang" | "kind" | "type" | "extension" | "ext" => Ok(Self::Language),
+            "path" | "paths" | "p" | "name" => Ok(Self::Path),
             "files" | "file" | "f" | "file-count" | "count" => Ok(Self::Files),
             "lines" | "line" | "l" => Ok(Self::Lines),
             "words" | "word" | "w" => Ok(Self::Words),
             "chars" | "char" | "c" => Ok(Self::Chars),
+            "graphemes" | "grapheme" => Ok(Self::Graphemes),
+            "width" | "display-width" | "columns" => Ok(Self::Width),
             "bytes" | "byte" | "b" => Ok(Self::Bytes),
+            "max-line-length" | "longest-line" | "line-length" => Ok(Self::MaxLineLength),
+            "max-line-width" | "widest-line" | "line-width" => Ok(Self::MaxLineWidth),
+            "avg-line-length" | "average-line-length" | "avg-line" => Ok(Self::AvgLineLength),
             _ => Err(format!("Invalid metric: {}", s)),
         }
     }
 }
 
+impl serde::Serialize for Metric {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl std::fmt::Display for Metric {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let name = match self {
             Self::Language => "language",
+            Self::Path => "path",
             Self::Files => "files",
             Self::Lines => "lines",
             Self::Words => "words",
             Self::Chars => "chars",
+            Self::Graphemes => "graphemes",
+            Self::Width => "width",
             Self::Bytes => "bytes",
+            Self::MaxLineLength => "max-line-length",
+            Self::MaxLineWidth => "max-line-width",
+            Self::AvgLineLength => "avg-line-length",
         };
         write!(f, "{}", name)
     }
 }
 
+/// What the bars in the graph column are scaled against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphScale {
+    /// The largest value across the results, so that the largest row has a full bar
+    Max,
+    /// The total across the results, so that each bar shows the row's share of the total
+    Total,
+    /// The logarithm of the largest value, so that the small values remain visible next to a huge one
+    Log,
+}
+
+impl std::str::FromStr for GraphScale {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "max" | "maximum" => Ok(Self::Max),
+            "total" | "sum" => Ok(Self::Total),
+            "log" | "logarithmic" => Ok(Self::Log),
+            x => Err(format!("Invalid graph scale: {x}")),
+        }
+    }
+}
+
+/// Where to show human-readable numbers (byte sizes in units and counts with thousands separators)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Human {
+    /// Only in the tables, keeping raw integers in the machine-readable formats
+    Table,
+    /// In every output format
+    All,
+}
+
+impl std::str::FromStr for Human {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(Self::Table),
+            "all" | "always" => Ok(Self::All),
+            x => Err(format!("Invalid human-readable mode: {x}")),
+        }
+    }
+}
+
+/// What the heat-map colours of the numeric cells are relative to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeatScale {
+    /// The largest value in the column
+    Max,
+    /// The rank of the value among the rows, so that the colours are spread evenly
+    Percentile,
+}
+
+impl std::str::FromStr for HeatScale {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "max" | "maximum" => Ok(Self::Max),
+            "percentile" | "rank" => Ok(Self::Percentile),
+            x => Err(format!("Invalid heat-map scale: {x}")),
+        }
+    }
+}
+
+/// The line terminator of the delimiter-separated formats
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineTerminator {
+    /// A line feed (`\n`), as is usual on Unix
+    #[default]
+    Lf,
+    /// A carriage return and a line feed (`\r\n`), as required by RFC 4180
+    CrLf,
+}
+
+impl LineTerminator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
+
+impl std::str::FromStr for LineTerminator {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "lf" | "unix" | "\n" | "\\n" => Ok(Self::Lf),
+            "crlf" | "windows" | "dos" | "\r\n" | "\\r\\n" => Ok(Self::CrLf),
+            x => Err(format!("Invalid line terminator: {x}")),
+        }
+    }
+}
+
+/// The kind of chart drawn by the [SVG][Format::SVG] output
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Chart {
+    /// A horizontal bar split into the shares of each language, with a legend
+    #[default]
+    Bar,
+    /// A pie chart of the shares of each language, with a legend
+    Pie,
+    /// A donut chart of the shares of each language, with the total in the middle
+    Donut,
+    /// A badge showing the total of the graph metric
+    Badge,
+}
+
+impl std::str::FromStr for Chart {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bar" => Ok(Self::Bar),
+            "pie" => Ok(Self::Pie),
+            "donut" | "doughnut" => Ok(Self::Donut),
+            "badge" | "shield" => Ok(Self::Badge),
+            x => Err(format!("Invalid chart: {x}")),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Format {
     Table,
     Plain,
     JSON,
+    NDJSON,
+    YAML,
+    TOML,
+    XML,
     TSV,
     CSV,
+    HTML,
+    SVG,
+    OpenMetrics,
+    SARIF,
+    Checkstyle,
 }
 
 impl std::str::FromStr for Format {
@@ -66,74 +330,353 @@ impl std::str::FromStr for Format {
             "table" => Ok(Self::Table),
             "plain" => Ok(Self::Plain),
             "json" => Ok(Self::JSON),
+            "ndjson" | "jsonl" | "json-lines" => Ok(Self::NDJSON),
+            "yaml" | "yml" => Ok(Self::YAML),
+            "toml" => Ok(Self::TOML),
+            "xml" => Ok(Self::XML),
             "tsv" => Ok(Self::TSV),
             "csv" => Ok(Self::CSV),
+            "html" => Ok(Self::HTML),
+            "svg" => Ok(Self::SVG),
+            "openmetrics" | "prometheus" | "prom" => Ok(Self::OpenMetrics),
+            "sarif" => Ok(Self::SARIF),
+            "checkstyle" => Ok(Self::Checkstyle),
             x => Err(format!("Unsupported Format: {x}")),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Config {
-    pub files: bool,
-
-    pub language: bool,
-    pub lines: bool,
-    pub words: bool,
-    pub chars: bool,
-    pub bytes: bool,
+    /// The columns to show, in display order
+    pub columns: Vec<Column>,
+    /// Serialize every field of the results in the structured formats (JSON, YAML, ...) instead of only the
+    /// selected columns. The selected percentage columns and human-readable numbers are still included.
+    pub all_fields: bool,
     pub group_by_language: bool,
 
-    pub sort_by: Metric,
-    pub sort_order: SortOrder,
+    /// The keys to sort the rows by, in order of priority
+    pub sort_by: Vec<SortKey>,
+    /// The order for the sort keys that don't specify one (defaults to each key's natural order)
+    pub sort_order: Option<SortOrder>,
 
-    pub graph: bool,
     pub graph_by: Metric,
+    pub graph_scale: GraphScale,
     pub graph_fill: String,
     pub graph_blank: String,
     pub graph_size: usize,
+    /// Draw the bars with eighth-blocks for a sub-character resolution
+    pub graph_smooth: bool,
+    /// Split the bar of the total row into the shares of each language
+    pub graph_stacked: bool,
 
+    /// The number of decimal places shown in the percentage columns
+    pub percent_precision: usize,
+
+    /// Where to format the numbers for humans, if at all
+    pub human: Option<Human>,
+    /// Use SI (powers of 1000) instead of binary (powers of 1024) units for the human-readable byte sizes
+    pub si: bool,
+    /// The separator between groups of thousands in the human-readable counts
+    pub thousands_separator: char,
+
+    /// Only show the first `top` rows (after sorting), aggregating the rest into an "Others" row
+    pub top: Option<usize>,
+    /// Only show the rows with at least this many lines
+    pub min_lines: Option<usize>,
+    /// Only show the rows with at least this many bytes
+    pub min_bytes: Option<u64>,
+
+    /// When set, list the lines longer than this limit instead of the usual report
+    pub max_line_length: Option<usize>,
+    /// When set, list the files with more lines than this limit instead of the usual report
+    pub max_file_lines: Option<usize>,
+
+    /// When set, show the distribution of the files over this metric instead of the usual report
+    pub histogram: Option<Metric>,
+    pub histogram_scale: Scale,
+    pub histogram_bins: usize,
+    /// Split each histogram bar into per-language segments
+    pub histogram_stacked: bool,
+
+    /// The borders and rules the tables are drawn with
+    pub table_style: TableStyle,
+    /// The display width the table has to fit in (usually the width of the terminal), if any
+    pub width: Option<usize>,
     pub header: bool,
     pub footer: bool,
+    /// Show the distribution statistics (min, mean, median, percentiles) as additional footer rows
+    pub stats: bool,
     pub alignment: bool,
 
-    pub use_colors: bool,
+    /// The separator of the delimiter-separated formats, instead of the default of the format (`,` or a tab)
+    pub delimiter: Option<String>,
+    pub line_terminator: LineTerminator,
+    /// Whether the footer rows of the delimiter-separated formats are part of the data. Otherwise, they are
+    /// [rendered separately][display_separately] so that the data imports cleanly into spreadsheets.
+    pub footer_in_data: bool,
+
+    /// The colours the output is painted in: those the terminal can display, which the theme's colours are
+    /// downgraded to, or [none][ColorSupport::None] when the colours are disabled
+    pub color_support: ColorSupport,
+    pub theme: Theme,
+    /// When set, colour the numeric cells of the table on a gradient, to make the biggest values stand out
+    pub heatmap: Option<HeatScale>,
+    /// When set, render the results with this template instead of the output format
+    pub template: Option<Template>,
+    /// The chart drawn by the SVG output, showing the shares (or the total) of the graph metric
+    pub chart: Chart,
+    /// The label of the SVG badge (defaults to the name of the graph metric)
+    pub badge_label: Option<String>,
+    /// The prefix of the metric names in the OpenMetrics output (e.g. `tali` for `tali_lines`)
+    pub metric_prefix: String,
+    /// The labels added to every sample of the OpenMetrics output, in order
+    pub metric_labels: Vec<(String, String)>,
     pub format: Format,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            files: true,
-
-            language: true,
-            lines: true,
-            words: true,
-            chars: true,
-            bytes: true,
+            columns: Column::DEFAULT.to_vec(),
+            all_fields: false,
             group_by_language: false,
 
-            sort_by: Metric::Bytes,
-            sort_order: SortOrder::Descending,
+            sort_by: vec![SortKey::from(Metric::Bytes)],
+            sort_order: None,
 
-            graph: true,
             graph_by: Metric::Bytes,
+            graph_scale: GraphScale::Max,
             graph_fill: "▬".into(),
             graph_blank: " ".into(),
             graph_size: 20,
+            graph_smooth: false,
+            graph_stacked: false,
+
+            percent_precision: 1,
+
+            human: None,
+            si: false,
+            thousands_separator: ',',
 
+            top: None,
+            min_lines: None,
+            min_bytes: None,
+
+            max_line_length: None,
+            max_file_lines: None,
+
+            histogram: None,
+            histogram_scale: Scale::Linear,
+            histogram_bins: 10,
+            histogram_stacked: false,
+
+            table_style: TableStyle::Plain,
+            width: None,
             header: true,
             footer: true,
+            stats: false,
             alignment: true,
+            delimiter: None,
+            line_terminator: LineTerminator::Lf,
+            footer_in_data: true,
 
-            use_colors: true,
+            color_support: ColorSupport::TrueColor,
+            theme: Theme::default(),
+            heatmap: None,
+            template: None,
+            chart: Chart::Bar,
+            badge_label: None,
+            metric_prefix: "tali".into(),
+            metric_labels: Vec::new(),
             format: Format::Table,
         }
     }
 }
 
+impl Config {
+    /// Whether the given column is selected
+    pub fn has(&self, column: Column) -> bool {
+        self.columns.contains(&column)
+    }
+
+    /// Whether the numbers should be formatted for humans in the current output format
+    pub fn humanize(&self) -> bool {
+        match self.human {
+            Some(Human::All) => true,
+            Some(Human::Table) => {
+                matches!(self.format, Format::Table | Format::Plain | Format::HTML)
+            }
+            None => false,
+        }
+    }
+
+    /// Whether to list the violations of the thresholds instead of the usual report, i.e. when a threshold is set
+    /// or the output format is a code-scanning report
+    pub fn check(&self) -> bool {
+        self.max_line_length.is_some()
+            || self.max_file_lines.is_some()
+            || matches!(self.format, Format::SARIF | Format::Checkstyle)
+    }
+
+    /// Whether the output is painted in colours at all
+    pub fn use_colors(&self) -> bool {
+        self.color_support != ColorSupport::None
+    }
+
+    /// The separator of the fields in the delimiter-separated formats
+    pub fn field_separator(&self) -> &str {
+        match (&self.delimiter, self.format) {
+            (Some(delimiter), _) => delimiter,
+            (None, Format::TSV) => "\t",
+            (None, _) => ",",
+        }
+    }
+
+    /// The selected columns that hold data, i.e. everything except the [graph][Column::Graph]
+    pub fn data_columns(&self) -> impl Iterator<Item = &Column> + Clone {
+        self.columns.iter().filter(|column| column.is_data())
+    }
+}
+
+/// Builds the distribution statistics footer rows for the given columns: the label in the language
+/// column (or the path column, if there is no language column) and the statistics in the numeric
+/// columns. The other columns are left empty. When grouped by language, the rows of the statistics
+/// across all files are followed by the rows of each language (labelled e.g. `Rust Median`).
+fn stats_rows<'a>(
+    results: &ScanResults,
+    columns: impl Iterator<Item = &'a Column> + Clone,
+    config: &Config,
+) -> Vec<Vec<String>> {
+    let mut rows = summary_rows("", &results.stats, columns.clone(), config);
+    for (language, stats) in &results.language_stats {
+        let prefix = format!("{language} ");
+        rows.extend(summary_rows(&prefix, stats, columns.clone(), config));
+    }
+    rows
+}
+
+/// Builds the rows of the statistics, with the labels preceded by the given prefix
+fn summary_rows<'a>(
+    prefix: &str,
+    stats: &Stats,
+    columns: impl Iterator<Item = &'a Column> + Clone,
+    config: &Config,
+) -> Vec<Vec<String>> {
+    let labels = Summary::default().labelled().map(|(label, _)| label);
+    let label_column = if columns.clone().any(|c| *c == Column::Language) {
+        Column::Language
+    } else {
+        Column::Path
+    };
+    (0..labels.len())
+        .map(|i| {
+            columns
+                .clone()
+                .map(|column| match column {
+                    column if *column == label_column => format!("{prefix}{}", labels[i]),
+                    column => column
+                        .metric()
+                        .and_then(|metric| stats.summary(metric))
+                        .map(|summary| column.stat(summary.labelled()[i].1, config))
+                        .unwrap_or_default(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Formats a statistic with (up to) two decimal places, dropping any trailing zeros
+fn format_stat(value: f64) -> String {
+    let formatted = format!("{:.2}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// The total of the metric for each language across the rows, largest first
+fn language_shares(results: &ScanResults, metric: Metric) -> Vec<(Language, f64)> {
+    let mut shares: Vec<(Language, f64)> = Vec::new();
+    for file in &results.files {
+        let value = file.value(metric).unwrap_or_default();
+        match shares
+            .iter_mut()
+            .find(|(language, _)| *language == file.language)
+        {
+            Some((_, share)) => *share += value,
+            None => shares.push((file.language.clone(), value)),
+        }
+    }
+    shares.sort_by(|a, b| b.1.total_cmp(&a.1));
+    shares
+}
+
 /// Formats the [`ScanResults`] based on the [`Config`] and returns the output
-pub fn display(results: ScanResults, mut config: Config) -> String {
+pub fn display(results: ScanResults, config: Config) -> String {
+    let terminator = config.line_terminator;
+    match display_separately(results, config) {
+        (data, Some(totals)) => data + terminator.as_str() + &totals,
+        (data, None) => data,
+    }
+}
+
+/// Formats the [`ScanResults`] like [`display`], and writes the output to the writer
+///
+/// ```
+/// use tali::{output::{self, Config, Format}, scanner::Scanner};
+///
+/// let (results, _) = Scanner::new().scan(&["Cargo.toml"]);
+/// let config = Config { format: Format::JSON, ..Default::default() };
+/// output::write(results, config, &mut std::io::stdout())?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn write(results: ScanResults, config: Config, writer: &mut impl Write) -> io::Result<()> {
+    let terminator = config.line_terminator;
+    if let Some(totals) = write_separately(results, config, writer)? {
+        writer.write_all(terminator.as_str().as_bytes())?;
+        writer.write_all(totals.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Formats the [`ScanResults`] like [`display`], but returns the footer rows of the delimiter-separated formats
+/// on their own when they are [kept out of the data][Config::footer_in_data]
+pub fn display_separately(results: ScanResults, config: Config) -> (String, Option<String>) {
+    let mut buffer = Vec::new();
+    // Writing into memory can't fail
+    let totals = write_separately(results, config, &mut buffer).unwrap_or_default();
+    (String::from_utf8_lossy(&buffer).into_owned(), totals)
+}
+
+/// Writes the [`ScanResults`] like [`write()`], but returns the footer rows of the delimiter-separated formats
+/// on their own when they are [kept out of the data][Config::footer_in_data]
+pub fn write_separately(
+    results: ScanResults,
+    mut config: Config,
+    writer: &mut dyn Write,
+) -> io::Result<Option<String>> {
+    // In check mode, list the offending lines and files of each individual file
+    if config.check() {
+        let mut results = results;
+        results.sort_by(&config.sort_by, config.sort_order);
+        CheckFormatter::default().write(&results, &config, writer)?;
+        return Ok(None);
+    }
+
+    // In histogram mode, bucket the individual files
+    if config.histogram.is_some() {
+        if let Format::Plain = config.format {
+            config.table_style = TableStyle::Plain;
+            config.header = false;
+            config.footer = false;
+            config.color_support = ColorSupport::None;
+            config.alignment = false;
+        }
+        HistogramFormatter::default().write(&results, &config, writer)?;
+        return Ok(None);
+    }
+
     // Reform ScanResults if we need to group by language
     let mut results = if config.group_by_language {
         results.group_by_language()
@@ -142,21 +685,129 @@ pub fn display(results: ScanResults, mut config: Config) -> String {
     };
 
     // Sort the results
-    results.sort_by(config.sort_by, &config.sort_order);
+    results.sort_by(&config.sort_by, config.sort_order);
+
+    // Filter the results, collapsing the rest into the "Others" row
+    let (min_lines, min_bytes) = (config.min_lines.unwrap_or(0), config.min_bytes.unwrap_or(0));
+    results.filter(config.top, |file| {
+        file.lines >= min_lines && file.bytes >= min_bytes
+    });
+
+    // Render the user-defined template instead of the output format, if there is one
+    if let Some(template) = &config.template {
+        TemplateFormatter::with(template).write(&results, &config, writer)?;
+        return Ok(None);
+    }
 
     // Chose the formatter based on the configuration
     match config.format {
-        Format::Table => TableFormatter::default().format(&results, &config),
+        Format::Table => TableFormatter::default().write(&results, &config, writer)?,
         Format::Plain => {
+            config.table_style = TableStyle::Plain;
+            config.width = None;
             config.header = false;
             config.footer = false;
-            config.graph = false;
-            config.use_colors = false;
+            config.stats = false;
+            config.columns.retain(|column| column.is_data());
+            config.color_support = ColorSupport::None;
             config.alignment = false;
-            TableFormatter::default().format(&results, &config)
+            TableFormatter::default().write(&results, &config, writer)?
         }
-        Format::JSON => JSONFormatter::default().format(&results, &config),
-        Format::TSV => DelimiterFormatter::with("\t").format(&results, &config),
-        Format::CSV => DelimiterFormatter::with(",").format(&results, &config),
+        Format::JSON => JSONFormatter::default().write(&results, &config, writer)?,
+        Format::NDJSON => NDJSONFormatter::default().write(&results, &config, writer)?,
+        Format::YAML => YAMLFormatter::default().write(&results, &config, writer)?,
+        Format::TOML => TOMLFormatter::default().write(&results, &config, writer)?,
+        Format::XML => XMLFormatter::default().write(&results, &config, writer)?,
+        Format::TSV | Format::CSV => {
+            let formatter = DelimiterFormatter::with(config.field_separator());
+            formatter.write(&results, &config, writer)?;
+            if !config.footer_in_data {
+                return Ok(Some(formatter.totals(&results, &config)));
+            }
+        }
+        Format::HTML => HTMLFormatter::default().write(&results, &config, writer)?,
+        Format::SVG => SVGFormatter::default().write(&results, &config, writer)?,
+        Format::OpenMetrics => OpenMetricsFormatter::default().write(&results, &config, writer)?,
+        Format::SARIF | Format::Checkstyle => unreachable!("the reports are written in check mode"),
+    }
+    Ok(None)
+}
+
+/// Writes a row for each file as soon as it is scanned, so that the output starts before the scan is over,
+/// and returns the results of the scan (with the footer rows kept apart from the data, like [`write_separately`]).
+///
+/// Only the formats with a row per file ([CSV][Format::CSV], [TSV][Format::TSV] and [NDJSON][Format::NDJSON])
+/// are streamed. Their rows are in the order the files are scanned (they aren't grouped, sorted or filtered),
+/// and the percentages and the graph are left out, as they depend on every file. The other formats (and the
+/// check, histogram and template modes) are written once all the files are scanned, as usual.
+///
+/// ```
+/// use tali::{output::{self, Config, Format}, scanner::Scanner};
+///
+/// let scanner = Scanner::new();
+/// let config = Config { format: Format::NDJSON, ..Default::default() };
+/// let mut ndjson = Vec::new();
+/// let (results, _) = output::stream(scanner.files(&["src"]).skip_errors(drop), &config, &mut ndjson)?;
+/// assert_eq!(ndjson.iter().filter(|byte| **byte == b'\n').count(), results.files.len());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn stream(
+    files: impl IntoIterator<Item = File>,
+    config: &Config,
+    writer: &mut dyn Write,
+) -> io::Result<(ScanResults, Option<String>)> {
+    let streams = matches!(config.format, Format::CSV | Format::TSV | Format::NDJSON)
+        && !config.check()
+        && config.histogram.is_none()
+        && config.template.is_none();
+    if !streams {
+        let results = files.into_iter().collect::<ScanResults>();
+        let totals = write_separately(results.clone(), config.clone(), writer)?;
+        return Ok((results, totals));
     }
+
+    let mut config = config.clone();
+    config
+        .columns
+        .retain(|column| column.is_data() && !matches!(column, Column::Percent(_)));
+    let delimiter = DelimiterFormatter::with(config.field_separator());
+    let formatter: &dyn RowFormatter = match config.format {
+        Format::NDJSON => &NDJSONFormatter::default(),
+        _ => &delimiter,
+    };
+
+    let mut results = ScanResults::default();
+    formatter.write_header(&config, writer)?;
+    writer.flush()?;
+    for file in files {
+        formatter.write_row(&file, &results, &config, writer)?;
+        writer.flush()?;
+        results.push(file);
+    }
+    formatter.write_footer(&results, &config, writer)?;
+
+    let totals = match config.format {
+        Format::CSV | Format::TSV if !config.footer_in_data => {
+            Some(delimiter.totals(&results, &config))
+        }
+        _ => None,
+    };
+    Ok((results, totals))
+}
+
+/// Scans the contents as files at the given paths (in the language of their extension), for the tests
+#[cfg(test)]
+pub(crate) fn scan_contents<'a>(
+    files: impl IntoIterator<Item = (&'a str, impl AsRef<str>)>,
+) -> ScanResults {
+    files
+        .into_iter()
+        .map(|(path, contents)| {
+            let reader = std::io::Cursor::new(contents.as_ref().to_string());
+            let mut file = File::scan_reader(reader).unwrap();
+            file.path = path.into();
+            file.language = crate::helpers::language::Language::from_path(path);
+            file
+        })
+        .collect()
 }
@@ -0,0 +1,258 @@
+use std::io::{self, Write};
+
+use crate::scanner::{File, ScanResults};
+
+use super::{Config, Formatter, Histogram, Metric};
+
+/// Renders the results in the OpenMetrics text exposition format (which Prometheus and node_exporter's
+/// textfile collector read), as gauges of each selected metric per language (e.g. `tali_lines{language="Rust"}`)
+/// and in total (e.g. `tali_lines_total`). Metrics that can't be summed (like the max line length) only have
+/// the per-language gauges.
+#[derive(Default, Debug)]
+pub struct OpenMetricsFormatter {}
+
+impl Formatter for OpenMetricsFormatter {
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        // The rows are already languages when grouped by language
+        let grouped;
+        let languages = if config.group_by_language {
+            &results.files
+        } else {
+            grouped = results.group_by_language();
+            &grouped.files
+        };
+        let mut languages = languages.iter().collect::<Vec<_>>();
+        languages.sort_by_key(|file| file.language.to_string());
+
+        for metric in self.metrics(config) {
+            let mut res = String::new();
+            let name = metric_name(metric, config);
+            res.push_str(&family(
+                &name,
+                &description(metric, "in each language"),
+                "gauge",
+            ));
+            for file in &languages {
+                let language = [("language", file.language.to_string())];
+                res.push_str(&sample(&name, &language, value(file, metric), config));
+            }
+
+            if let Some(total) = results.total.value(metric) {
+                let name = format!("{name}_total");
+                res.push_str(&family(&name, &description(metric, "in total"), "gauge"));
+                res.push_str(&sample(&name, &[], total, config));
+            }
+            writer.write_all(res.as_bytes())?;
+        }
+        writer.write_all(b"# EOF\n")
+    }
+}
+
+impl OpenMetricsFormatter {
+    /// The number of files, and the numeric metrics of the selected columns
+    fn metrics(&self, config: &Config) -> Vec<Metric> {
+        let mut metrics = vec![Metric::Files];
+        for metric in config.data_columns().filter_map(|column| column.metric()) {
+            if !metrics.contains(&metric) {
+                metrics.push(metric);
+            }
+        }
+        metrics
+    }
+}
+
+/// Renders a histogram as an OpenMetrics histogram of the files (e.g. `tali_file_lines_bucket{le="99"}`),
+/// with the cumulative number of files up to the (inclusive) upper boundary of each bucket
+pub(super) fn histogram(histogram: &Histogram, results: &ScanResults, config: &Config) -> String {
+    let name = format!(
+        "{}_file_{}",
+        config.metric_prefix,
+        histogram.metric.to_string().replace('-', "_")
+    );
+    let mut res = family(
+        &name,
+        &format!("The distribution of the {} of the files", histogram.metric),
+        "histogram",
+    );
+
+    let mut cumulative = 0;
+    for bucket in &histogram.buckets {
+        cumulative += bucket.count;
+        let le = [("le", bucket.upper.saturating_sub(1).to_string())];
+        res.push_str(&sample(
+            &format!("{name}_bucket"),
+            &le,
+            cumulative as f64,
+            config,
+        ));
+    }
+    let infinity = [("le", "+Inf".to_string())];
+    res.push_str(&sample(
+        &format!("{name}_bucket"),
+        &infinity,
+        cumulative as f64,
+        config,
+    ));
+
+    let sum = results
+        .files
+        .iter()
+        .map(|file| file.value(histogram.metric).unwrap_or_default().round())
+        .sum::<f64>();
+    res.push_str(&sample(
+        &format!("{name}_count"),
+        &[],
+        cumulative as f64,
+        config,
+    ));
+    res.push_str(&sample(&format!("{name}_sum"), &[], sum, config));
+    res.push_str("# EOF\n");
+    res
+}
+
+/// The name of the metric, e.g. `tali_max_line_length`
+fn metric_name(metric: Metric, config: &Config) -> String {
+    format!(
+        "{}_{}",
+        config.metric_prefix,
+        metric.to_string().replace('-', "_")
+    )
+}
+
+/// The help text of the metric
+fn description(metric: Metric, scope: &str) -> String {
+    let description = match metric {
+        Metric::Files => "The number of files",
+        Metric::Lines => "The number of lines",
+        Metric::Words => "The number of words",
+        Metric::Chars => "The number of unicode characters",
+        Metric::Graphemes => "The number of grapheme clusters",
+        Metric::Width => "The display width in terminal columns",
+        Metric::Bytes => "The size in bytes",
+        Metric::MaxLineLength => "The number of characters in the longest line",
+        Metric::MaxLineWidth => "The display width of the widest line",
+        Metric::AvgLineLength => "The average number of characters per line",
+        Metric::Language | Metric::Path => "",
+    };
+    format!("{description} {scope}")
+}
+
+/// The value of the metric for the (language) row
+fn value(file: &File, metric: Metric) -> f64 {
+    match metric {
+        Metric::Files => file.count as f64,
+        metric => file.value(metric).unwrap_or_default(),
+    }
+}
+
+/// The metadata lines of a metric family
+fn family(name: &str, help: &str, kind: &str) -> String {
+    format!("# HELP {name} {help}\n# TYPE {name} {kind}\n")
+}
+
+/// A sample of a metric, with the given labels followed by the [static labels][Config::metric_labels]
+fn sample(name: &str, labels: &[(&str, String)], value: f64, config: &Config) -> String {
+    let labels = labels
+        .iter()
+        .map(|(key, value)| (*key, value.as_str()))
+        .chain(
+            config
+                .metric_labels
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        )
+        .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+        .collect::<Vec<_>>();
+    if labels.is_empty() {
+        format!("{name} {value}\n")
+    } else {
+        format!("{name}{{{}}} {value}\n", labels.join(","))
+    }
+}
+
+/// Escapes the backslashes, quotes and line feeds in a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+/// Whether the name is a valid metric or label name (letters, digits and underscores, not starting with a digit)
+pub fn is_valid_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{Column, Scale, scan_contents};
+
+    fn results() -> ScanResults {
+        scan_contents([("a.rs", "1\n2\n3"), ("b.rs", "1"), ("c.md", "# \"x\"\n")])
+    }
+
+    #[test]
+    fn test_openmetrics_gauges() {
+        let config = Config {
+            columns: vec![Column::Path, Column::Lines, Column::MaxLineLength],
+            metric_labels: vec![("repo".into(), "a \"b\"".into())],
+            ..Default::default()
+        };
+        let output = OpenMetricsFormatter::default().format(&results(), &config);
+        let expected = r#"# HELP tali_files The number of files in each language
+# TYPE tali_files gauge
+tali_files{language="Markdown",repo="a \"b\""} 1
+tali_files{language="Rust",repo="a \"b\""} 2
+# HELP tali_files_total The number of files in total
+# TYPE tali_files_total gauge
+tali_files_total{repo="a \"b\""} 3
+# HELP tali_lines The number of lines in each language
+# TYPE tali_lines gauge
+tali_lines{language="Markdown",repo="a \"b\""} 1
+tali_lines{language="Rust",repo="a \"b\""} 4
+# HELP tali_lines_total The number of lines in total
+# TYPE tali_lines_total gauge
+tali_lines_total{repo="a \"b\""} 5
+# HELP tali_max_line_length The number of characters in the longest line in each language
+# TYPE tali_max_line_length gauge
+tali_max_line_length{language="Markdown",repo="a \"b\""} 5
+tali_max_line_length{language="Rust",repo="a \"b\""} 1
+# EOF
+"#;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_openmetrics_histogram() {
+        let results = results();
+        let histogram = Histogram::new(&results.files, Metric::Lines, Scale::Linear, 2);
+        let config = Config {
+            metric_prefix: "code".into(),
+            ..Default::default()
+        };
+        let output = super::histogram(&histogram, &results, &config);
+        assert!(output.starts_with("# HELP code_file_lines The distribution of the lines of the files\n# TYPE code_file_lines histogram\n"));
+        assert!(output.contains("code_file_lines_bucket{le=\"+Inf\"} 3\n"));
+        assert!(output.contains("code_file_lines_count 3\ncode_file_lines_sum 5\n"));
+        assert!(output.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_valid_names() {
+        assert!(is_valid_metric_name("tali"));
+        assert!(is_valid_metric_name("_code_size2"));
+        assert!(!is_valid_metric_name("2fast"));
+        assert!(!is_valid_metric_name("with-dash"));
+        assert!(!is_valid_metric_name(""));
+    }
+}
@@ -0,0 +1,239 @@
+use std::io::{self, Write};
+
+use serde_json::Value;
+
+use crate::scanner::ScanResults;
+
+use super::{Config, Formatter, JSONFormatter, html};
+
+/// Renders the results as YAML, with the same structure as the [JSON output][JSONFormatter]
+#[derive(Default, Debug)]
+pub struct YAMLFormatter {}
+
+impl Formatter for YAMLFormatter {
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let value = JSONFormatter::default().jsonify(results, config);
+        serde_norway::to_writer(writer, &value).map_err(io::Error::other)
+    }
+}
+
+/// Renders the results as TOML, with the same structure as the [JSON output][JSONFormatter]
+#[derive(Default, Debug)]
+pub struct TOMLFormatter {}
+
+impl Formatter for TOMLFormatter {
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let value = JSONFormatter::default().jsonify(results, config);
+        writer.write_all(to_toml(value)?.as_bytes())
+    }
+}
+
+/// Renders the results as XML, with the same structure as the [JSON output][JSONFormatter]
+#[derive(Default, Debug)]
+pub struct XMLFormatter {}
+
+impl Formatter for XMLFormatter {
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let value = JSONFormatter::default().jsonify(results, config);
+        writer.write_all(to_xml("tali", &value).as_bytes())
+    }
+}
+
+/// Serializes the value as TOML. TOML has no null, so the null values are left out.
+pub(super) fn to_toml(mut value: Value) -> io::Result<String> {
+    strip_nulls(&mut value);
+    toml::to_string_pretty(&value).map_err(io::Error::other)
+}
+
+/// Removes the null values from the objects (recursively)
+fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(values) => values.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+/// Serializes the value as an XML document with the given root element
+///
+/// The keys of the objects become the element names. The items of an array are wrapped in elements named after
+/// the singular of the array (e.g. `<files><file>...</file></files>`). Keys that are not valid element names
+/// (like the names of languages in the statistics) are written as `<entry key="...">` instead.
+pub(super) fn to_xml(root: &str, value: &Value) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    write_element(&mut xml, root, value, 0);
+    xml
+}
+
+fn write_element(xml: &mut String, name: &str, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let (open, close) = if is_xml_name(name) {
+        (name.to_string(), name)
+    } else {
+        (format!("entry key=\"{}\"", escape_xml(name)), "entry")
+    };
+
+    match value {
+        Value::Null => xml.push_str(&format!("{indent}<{open}/>\n")),
+        Value::Object(map) if map.is_empty() => xml.push_str(&format!("{indent}<{open}/>\n")),
+        Value::Array(values) if values.is_empty() => xml.push_str(&format!("{indent}<{open}/>\n")),
+        Value::Object(map) => {
+            xml.push_str(&format!("{indent}<{open}>\n"));
+            for (key, value) in map {
+                write_element(xml, key, value, depth + 1);
+            }
+            xml.push_str(&format!("{indent}</{close}>\n"));
+        }
+        Value::Array(values) => {
+            xml.push_str(&format!("{indent}<{open}>\n"));
+            let item = singular(close);
+            for value in values {
+                write_element(xml, &item, value, depth + 1);
+            }
+            xml.push_str(&format!("{indent}</{close}>\n"));
+        }
+        Value::String(text) => {
+            xml.push_str(&format!("{indent}<{open}>{}</{close}>\n", escape_xml(text)))
+        }
+        value => xml.push_str(&format!("{indent}<{open}>{value}</{close}>\n")),
+    }
+}
+
+/// Escapes the characters that have a special meaning in XML text and attributes. The C0 control characters other
+/// than tab, line feed and carriage return can't appear in an XML 1.0 document (not even as character references),
+/// so they are replaced with `U+FFFD`.
+pub(super) fn escape_xml(text: &str) -> String {
+    let text = text
+        .chars()
+        .map(|c| match c {
+            '\t' | '\n' | '\r' => c,
+            c if c < ' ' => char::REPLACEMENT_CHARACTER,
+            c => c,
+        })
+        .collect::<String>();
+    html::escape(&text)
+}
+
+/// The name of the items of an array, e.g. `file` for `files`
+fn singular(name: &str) -> String {
+    match name.strip_suffix('s') {
+        Some(singular) if !singular.is_empty() => singular.to_string(),
+        _ => "item".to_string(),
+    }
+}
+
+/// Whether the name can be used as an XML element name (conservatively, an ASCII identifier)
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !name.to_ascii_lowercase().starts_with("xml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{Column, Metric};
+    use crate::scanner::File;
+
+    fn results() -> ScanResults {
+        let mut results = ScanResults::default();
+        let mut file = File::scan_reader(std::io::Cursor::new("one two\nthree")).unwrap();
+        file.path = "a&b.rs".into();
+        results.total.add(&file);
+        results.max.track(&file);
+        results.stats.track(&file);
+        results.files.push(file);
+        results
+    }
+
+    fn config() -> Config {
+        Config {
+            columns: vec![Column::Lines, Column::Percent(Metric::Lines), Column::Graph],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_yaml_honours_selected_columns() {
+        let output = YAMLFormatter::default().format(&results(), &config());
+        let value: Value = serde_norway::from_str(&output).unwrap();
+        assert_eq!(
+            value,
+            JSONFormatter::default().jsonify(&results(), &config())
+        );
+        assert!(output.starts_with("files:\n- lines: 2\n  lines_percent: 100.0\n  path: a&b.rs\n"));
+    }
+
+    #[test]
+    fn test_toml_honours_selected_columns() {
+        let output = TOMLFormatter::default().format(&results(), &config());
+        let value: Value = toml::from_str(&output).unwrap();
+        assert_eq!(
+            value,
+            JSONFormatter::default().jsonify(&results(), &config())
+        );
+        assert!(
+            output.contains("[[files]]\nlines = 2\nlines_percent = 100.0\npath = \"a&b.rs\"\n")
+        );
+    }
+
+    #[test]
+    fn test_toml_reports_serialization_errors() {
+        // A TOML document must be a table
+        assert!(to_toml(serde_json::json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn test_xml_honours_selected_columns() {
+        let output = XMLFormatter::default().format(&results(), &config());
+        assert!(output.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<tali>\n"));
+        assert!(output.contains(
+            "    <file>\n      <lines>2</lines>\n      <lines_percent>100.0</lines_percent>\n      <path>a&amp;b.rs</path>\n"
+        ));
+        assert!(
+            output.contains("  <total>\n    <files>1</files>\n    <lines>2</lines>\n  </total>\n")
+        );
+        assert!(!output.contains("<words>"));
+        assert!(output.ends_with("</tali>\n"));
+    }
+
+    #[test]
+    fn test_xml_replaces_control_characters() {
+        assert_eq!(
+            escape_xml("a\u{1b}[0m\tb\n<c>\0"),
+            "a\u{fffd}[0m\tb\n&lt;c&gt;\u{fffd}"
+        );
+        let value = serde_json::json!({ "path": "bell\u{7}.rs" });
+        assert!(to_xml("root", &value).contains("  <path>bell\u{fffd}.rs</path>\n"));
+    }
+
+    #[test]
+    fn test_xml_element_names() {
+        let value = serde_json::json!({ "Rust": 1, ".md": 2, "list": [] });
+        let output = to_xml("root", &value);
+        assert!(output.contains("  <Rust>1</Rust>\n"));
+        assert!(output.contains("  <entry key=\".md\">2</entry>\n"));
+        assert!(output.contains("  <list/>\n"));
+    }
+}
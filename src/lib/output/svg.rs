@@ -0,0 +1,347 @@
+use std::{
+    f64::consts::PI,
+    io::{self, Write},
+};
+
+use crate::{
+    helpers::{language::Language, number},
+    scanner::ScanResults,
+};
+
+use super::{
+    Chart, Column, Config, Formatter, Histogram, Metric, html::hex, language_shares,
+    structured::escape_xml,
+};
+
+/// The font of the text in the charts (the badge uses the font of shields.io instead)
+const FONT: &str = "-apple-system,BlinkMacSystemFont,Segoe UI,Helvetica,Arial,sans-serif";
+/// The width of the bar chart and the histogram
+const WIDTH: f64 = 480.0;
+/// The height of each row of the legend
+const ROW: f64 = 20.0;
+/// The colour of the chart when there is nothing to show
+const EMPTY: &str = "#e1e4e8";
+
+/// Renders the shares of each language in the [graph metric][Config::graph_by] as an SVG [chart][Chart],
+/// or the total of the metric as a badge. The charts are drawn locally and need no external service.
+#[derive(Default, Debug)]
+pub struct SVGFormatter {}
+
+impl Formatter for SVGFormatter {
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let shares = language_shares(results, config.graph_by)
+            .into_iter()
+            .filter(|(_, share)| *share > 0.0)
+            .collect::<Vec<_>>();
+        let svg = match config.chart {
+            Chart::Bar => self.bar(&shares, config),
+            Chart::Pie => self.pie(&shares, None, config),
+            Chart::Donut => self.pie(&shares, Some(total(results, config)), config),
+            Chart::Badge => self.badge(&label(config), &total(results, config)),
+        };
+        writer.write_all(svg.as_bytes())
+    }
+}
+
+impl SVGFormatter {
+    /// A horizontal bar split into the shares of each language (like the language bar on GitHub),
+    /// with a legend of three columns below it
+    fn bar(&self, shares: &[(Language, f64)], config: &Config) -> String {
+        let sum = shares.iter().map(|(_, share)| share).sum::<f64>();
+        let mut body = format!(
+            "<clipPath id=\"bar\"><rect width=\"{WIDTH}\" height=\"8\" rx=\"4\"/></clipPath>\n\
+            <g clip-path=\"url(#bar)\">\n<rect width=\"{WIDTH}\" height=\"8\" fill=\"{EMPTY}\"/>\n"
+        );
+        let mut x = 0.0;
+        for (language, share) in shares {
+            let width = share / sum * WIDTH;
+            body.push_str(&format!(
+                "<rect x=\"{x:.2}\" width=\"{width:.2}\" height=\"8\" fill=\"{}\"><title>{}</title></rect>\n",
+                hex(language.color()),
+                escape_xml(&language.to_string())
+            ));
+            x += width;
+        }
+        body.push_str("</g>\n");
+
+        let columns = 3;
+        for (i, (language, share)) in shares.iter().enumerate() {
+            let (x, y) = (
+                (i % columns) as f64 * WIDTH / columns as f64,
+                20.0 + (i / columns) as f64 * ROW,
+            );
+            body.push_str(&legend(x, y, language, share / sum * 100.0, config));
+        }
+
+        let rows = shares.len().div_ceil(columns);
+        let height = 20.0 + rows as f64 * ROW;
+        svg(WIDTH, height, "Languages", &body)
+    }
+
+    /// A pie chart of the shares of each language, with a legend on the right. With a total,
+    /// the pie is hollowed into a donut with the total in the middle.
+    ///
+    /// Each slice is a circle with a dashed stroke, where the dash spans the slice's share of the circumference.
+    fn pie(&self, shares: &[(Language, f64)], total: Option<String>, config: &Config) -> String {
+        let (size, center) = (200.0, 100.0);
+        // A pie is a circle whose stroke is as wide as its diameter, so that it fills in the middle
+        let (radius, stroke) = match total {
+            Some(_) => (70.0, 40.0),
+            None => (45.0, 90.0),
+        };
+        let circumference = 2.0 * PI * radius;
+        let circle = |color: &str| {
+            format!(
+                "<circle cx=\"{center}\" cy=\"{center}\" r=\"{radius}\" fill=\"none\" stroke=\"{color}\" \
+                stroke-width=\"{stroke}\""
+            )
+        };
+
+        let sum = shares.iter().map(|(_, share)| share).sum::<f64>();
+        let mut body = circle(EMPTY) + "/>\n";
+        body.push_str(&format!(
+            "<g transform=\"rotate(-90 {center} {center})\">\n"
+        ));
+        let mut offset = 0.0;
+        for (language, share) in shares {
+            let length = share / sum * circumference;
+            body.push_str(&format!(
+                "{} stroke-dasharray=\"{length:.2} {circumference:.2}\" stroke-dashoffset=\"{:.2}\">\
+                <title>{}</title></circle>\n",
+                circle(&hex(language.color())),
+                -offset,
+                escape_xml(&language.to_string())
+            ));
+            offset += length;
+        }
+        body.push_str("</g>\n");
+
+        if let Some(total) = total {
+            body.push_str(&format!(
+                "<text x=\"{center}\" y=\"{center}\" text-anchor=\"middle\" font-family=\"{FONT}\" \
+                font-size=\"20\" font-weight=\"bold\" fill=\"#24292f\">{}</text>\n\
+                <text x=\"{center}\" y=\"{}\" text-anchor=\"middle\" font-family=\"{FONT}\" \
+                font-size=\"12\" fill=\"#57606a\">{}</text>\n",
+                escape_xml(&total),
+                center + 18.0,
+                escape_xml(&label(config))
+            ));
+        }
+
+        for (i, (language, share)) in shares.iter().enumerate() {
+            let y = 10.0 + i as f64 * ROW;
+            body.push_str(&legend(
+                size + 20.0,
+                y,
+                language,
+                share / sum * 100.0,
+                config,
+            ));
+        }
+
+        let height = f64::max(size, 10.0 + shares.len() as f64 * ROW);
+        svg(size + 200.0, height, "Languages", &body)
+    }
+
+    /// A shields-style badge with the label on the left, and the value on the right
+    fn badge(&self, label: &str, value: &str) -> String {
+        // Estimate the width of the text, as the badge can't measure it (about 7 pixels per character at 11px)
+        let width = |text: &str| text.chars().count() as f64 * 7.0 + 10.0;
+        let (label_width, value_width) = (width(label), width(value));
+        let total_width = label_width + value_width;
+        let text = |x: f64, text: &str| {
+            let text = escape_xml(text);
+            format!(
+                "<text x=\"{x:.1}\" y=\"15\" fill=\"#010101\" fill-opacity=\".3\">{text}</text>\
+                <text x=\"{x:.1}\" y=\"14\">{text}</text>\n"
+            )
+        };
+
+        let body = format!(
+            "<linearGradient id=\"shine\" x2=\"0\" y2=\"100%\">\
+            <stop offset=\"0\" stop-color=\"#bbb\" stop-opacity=\".1\"/><stop offset=\"1\" stop-opacity=\".1\"/>\
+            </linearGradient>\n\
+            <clipPath id=\"round\"><rect width=\"{total_width}\" height=\"20\" rx=\"3\"/></clipPath>\n\
+            <g clip-path=\"url(#round)\">\
+            <rect width=\"{label_width}\" height=\"20\" fill=\"#555\"/>\
+            <rect x=\"{label_width}\" width=\"{value_width}\" height=\"20\" fill=\"#007ec6\"/>\
+            <rect width=\"{total_width}\" height=\"20\" fill=\"url(#shine)\"/></g>\n\
+            <g fill=\"#fff\" text-anchor=\"middle\" font-family=\"Verdana,Geneva,DejaVu Sans,sans-serif\" font-size=\"11\">\n\
+            {}{}</g>\n",
+            text(label_width / 2.0, label),
+            text(label_width + value_width / 2.0, value),
+        );
+        svg(total_width, 20.0, &format!("{label}: {value}"), &body)
+    }
+}
+
+/// Renders a histogram as an SVG chart of horizontal bars, with the bucket ranges on the left
+/// and the number of files on the right of each bar
+pub(super) fn histogram(histogram: &Histogram, config: &Config) -> String {
+    let (labels, bars) = (100.0, 320.0);
+    let max = histogram
+        .buckets
+        .iter()
+        .map(|bucket| bucket.count)
+        .max()
+        .unwrap_or_default();
+
+    let mut body = format!("<g font-family=\"{FONT}\" font-size=\"12\" fill=\"#24292f\">\n");
+    for (i, bucket) in histogram.buckets.iter().enumerate() {
+        let y = i as f64 * ROW;
+        let width = if max > 0 {
+            bucket.count as f64 / max as f64 * bars
+        } else {
+            0.0
+        };
+        body.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\
+            <rect x=\"{labels}\" y=\"{}\" width=\"{width:.2}\" height=\"14\" rx=\"2\" fill=\"#0969da\"/>\
+            <text x=\"{:.2}\" y=\"{}\">{}</text>\n",
+            labels - 8.0,
+            y + 12.0,
+            escape_xml(&bucket.label()),
+            y + 2.0,
+            labels + width + 6.0,
+            y + 12.0,
+            bucket.count
+        ));
+    }
+    body.push_str("</g>\n");
+
+    let metric = Column::from(histogram.metric).header(config);
+    let title = format!("Files by {}", metric.to_lowercase());
+    let height = histogram.buckets.len() as f64 * ROW;
+    svg(WIDTH, height, &title, &body)
+}
+
+/// An entry of the legend: the language's colour, name and share
+fn legend(x: f64, y: f64, language: &Language, percent: f64, config: &Config) -> String {
+    format!(
+        "<g font-family=\"{FONT}\" font-size=\"12\"><circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"5\" fill=\"{}\"/>\
+        <text x=\"{:.2}\" y=\"{:.2}\" fill=\"#24292f\">{} <tspan fill=\"#57606a\">{:.*}%</tspan></text></g>\n",
+        x + 5.0,
+        y + 6.0,
+        hex(language.color()),
+        x + 14.0,
+        y + 10.0,
+        escape_xml(&language.to_string()),
+        config.percent_precision,
+        percent
+    )
+}
+
+/// Wraps the body in an SVG document of the given size, with a title for accessibility
+fn svg(width: f64, height: f64, title: &str, body: &str) -> String {
+    let title = escape_xml(title);
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+        viewBox=\"0 0 {width} {height}\" role=\"img\" aria-label=\"{title}\">\n\
+        <title>{title}</title>\n{body}</svg>\n"
+    )
+}
+
+/// The total of the graph metric across the results (or the max, for metrics that can't be summed),
+/// abbreviated to fit in a badge
+fn total(results: &ScanResults, config: &Config) -> String {
+    let metric = config.graph_by;
+    let value = results
+        .total
+        .value(metric)
+        .or(results.max.value(metric))
+        .unwrap_or_default() as u64;
+    match metric {
+        Metric::Bytes => number::format_bytes(value, config.si, config.thousands_separator),
+        _ => number::abbreviate(value),
+    }
+}
+
+/// The label of the total, e.g. `lines`
+fn label(config: &Config) -> String {
+    config
+        .badge_label
+        .clone()
+        .unwrap_or_else(|| Column::from(config.graph_by).header(config).to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::scan_contents;
+
+    fn results() -> ScanResults {
+        scan_contents([
+            ("main.rs", "1\n2\n3\n"),
+            ("lib.rs", "4\n"),
+            ("README.md", "# tali\n\n..\n..\n"),
+        ])
+    }
+
+    fn render(chart: Chart) -> String {
+        let config = Config {
+            chart,
+            graph_by: Metric::Lines,
+            ..Default::default()
+        };
+        SVGFormatter::default().format(&results(), &config)
+    }
+
+    #[test]
+    fn test_bar_chart() {
+        let output = render(Chart::Bar);
+        assert!(output.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(output.ends_with("</svg>\n"));
+        // Rust and Markdown have 4 lines each, so they split the bar evenly
+        assert!(output.contains(&format!(
+            "<rect x=\"0.00\" width=\"240.00\" height=\"8\" fill=\"{}\">",
+            hex(Language::Rust.color())
+        )));
+        assert!(output.contains("<rect x=\"240.00\" width=\"240.00\""));
+        assert_eq!(output.matches("50.0%").count(), 2);
+    }
+
+    #[test]
+    fn test_pie_and_donut_charts() {
+        let pie = render(Chart::Pie);
+        assert_eq!(pie.matches("stroke-dasharray").count(), 2);
+        assert!(pie.contains("stroke-width=\"90\""));
+        assert!(pie.contains("stroke-dashoffset=\"-141.37\""));
+
+        let donut = render(Chart::Donut);
+        assert!(donut.contains("stroke-width=\"40\""));
+        assert!(donut.contains(">8</text>"));
+        assert!(donut.contains(">lines</text>"));
+    }
+
+    #[test]
+    fn test_badge() {
+        let config = Config {
+            chart: Chart::Badge,
+            graph_by: Metric::Lines,
+            badge_label: Some("lines of <code>".into()),
+            ..Default::default()
+        };
+        let output = SVGFormatter::default().format(&results(), &config);
+        assert!(output.contains("aria-label=\"lines of &lt;code&gt;: 8\""));
+        assert!(output.contains("<text x=\"57.5\" y=\"14\">lines of &lt;code&gt;</text>"));
+        assert!(output.contains(">8</text>"));
+        assert!(output.contains("width=\"132\" height=\"20\""));
+    }
+
+    #[test]
+    fn test_empty_chart() {
+        let config = Config {
+            chart: Chart::Pie,
+            ..Default::default()
+        };
+        let output = SVGFormatter::default().format(&ScanResults::default(), &config);
+        assert!(output.contains(EMPTY));
+        assert!(!output.contains("stroke-dasharray"));
+        assert!(!output.contains("NaN"));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     helpers::{
+        ansi::repeat_to_width,
         language::Language,
         path,
         table::{Alignment, Table},
@@ -7,20 +8,127 @@ use crate::{
     scanner::{File, ScanResults},
 };
 
-use super::{Config, Formatter, Metric};
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use super::{
+    Color, Column, Config, Formatter, GraphScale, HeatScale, Metric, language_shares, stats_rows,
+};
+
+/// The blocks that fill one to seven eighths of a character cell, for the end of a smooth bar
+const PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// The fills that tell the segments of a stacked bar apart when their colours don't
+const SEGMENT_FILLS: [&str; 4] = ["█", "▓", "▒", "░"];
+
+/// The narrowest the graph is shrunk to before the paths are truncated
+const MIN_GRAPH_SIZE: usize = 5;
+/// The narrowest the paths are truncated to before columns are dropped
+const MIN_PATH_WIDTH: usize = 16;
 
 #[derive(Debug, Default)]
-pub struct TableFormatter {}
+pub struct TableFormatter {
+    /// When set, the paths are truncated (in the middle) to this display width
+    path_width: Option<usize>,
+}
 
 impl Formatter for TableFormatter {
-    fn format(&self, results: &ScanResults, config: &Config) -> String {
-        let mut res = String::new();
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let table = match config.width {
+            Some(width) => self.fit(results, config, width).display(),
+            None => self.build_table(results, config).display(),
+        };
+        writer.write_all(table.as_bytes())
+    }
+}
+
+impl TableFormatter {
+    /// Lays the table out to fit within the given width. The graph is shrunk first, then the paths are
+    /// truncated, and if the table still doesn't fit, the columns are dropped in order of [priority][Column::priority].
+    fn fit(&self, results: &ScanResults, config: &Config, width: usize) -> Table {
+        let mut config = config.clone();
+        loop {
+            let mut table = self.build_table(results, &config);
+            let overflow = table.width().saturating_sub(width);
+            if overflow == 0 {
+                return table;
+            }
+
+            if config.has(Column::Graph) && config.graph_size > MIN_GRAPH_SIZE {
+                config.graph_size = config
+                    .graph_size
+                    .saturating_sub(overflow)
+                    .max(MIN_GRAPH_SIZE);
+                continue;
+            }
+
+            let path = config
+                .columns
+                .iter()
+                .position(|column| *column == Column::Path && !config.group_by_language);
+            let path_width = path.map(|i| table.widths()[i].saturating_sub(overflow));
+            if let Some(path_width) = path_width.filter(|w| *w >= MIN_PATH_WIDTH) {
+                return Self::truncated(path_width).build_table(results, &config);
+            }
 
+            // Drop the least important column (never the one that identifies the rows)
+            let identity = if config.group_by_language {
+                Column::Language
+            } else {
+                Column::Path
+            };
+            let droppable = config
+                .columns
+                .iter()
+                .enumerate()
+                .filter(|(_, column)| **column != identity)
+                .min_by_key(|(_, column)| column.priority())
+                .map(|(i, _)| i);
+            match droppable {
+                Some(i) if config.columns.len() > 2 => {
+                    config.columns.remove(i);
+                }
+                _ => {
+                    let formatter = match path_width {
+                        Some(_) => Self::truncated(MIN_PATH_WIDTH),
+                        None => Self::default(),
+                    };
+                    return formatter.build_table(results, &config);
+                }
+            }
+        }
+    }
+
+    /// A formatter that truncates the paths to the given width
+    fn truncated(path_width: usize) -> Self {
+        Self {
+            path_width: Some(path_width),
+        }
+    }
+
+    fn build_table(&self, results: &ScanResults, config: &Config) -> Table {
+        let mut table = Table::default();
+        table
+            .with_style(config.table_style)
+            .with_border_escape(config.theme.separator.escape(config.color_support));
+
+        let heatmap = config
+            .heatmap
+            .map(|scale| Heatmap::new(scale, results, config));
         for file in &results.files {
-            res.push_str(&self.build_row(file, &results, config));
+            table.add_row(self.build_row(file, results, config, heatmap.as_ref()));
         }
 
-        let mut table = Table::from_tsv(&res);
+        if let Some(others) = &results.others {
+            table.add_row(self.build_others_row(others, results, config));
+        }
 
         if config.header {
             let header = self.build_header(config);
@@ -28,170 +136,607 @@ impl Formatter for TableFormatter {
         }
 
         if config.footer {
-            let footer = self.build_footer(&results, config);
+            let footer = self.build_footer(results, config);
             table.with_footer(footer);
         }
 
+        if config.stats {
+            for row in stats_rows(results, config.columns.iter(), config) {
+                let row = row
+                    .iter()
+                    .map(|cell| config.theme.footer.paint(cell, config.color_support))
+                    .collect();
+                table.add_footer_row(row);
+            }
+        }
+
         if config.alignment {
             let alignments = self.build_alignments(config);
             table.with_alignments(alignments);
         }
 
-        table.display()
+        table
     }
-}
 
-impl TableFormatter {
-    // Helper function to select columns
-    fn selected_columns<T>(&self, values: T, config: &Config) -> Vec<T::Item>
-    where
-        T: IntoIterator,
-        T::IntoIter: ExactSizeIterator,
-        T::Item: Clone,
-    {
-        let options = [
-            config.language,
-            config.files,
-            config.lines,
-            config.words,
-            config.chars,
-            config.bytes,
-            config.graph,
-        ];
-        values
-            .into_iter()
-            .enumerate()
-            .filter_map(|(i, v)| options[i].then_some(v.clone()))
+    fn build_header(&self, config: &Config) -> Vec<String> {
+        config
+            .columns
+            .iter()
+            .map(|column| {
+                config
+                    .theme
+                    .header
+                    .paint(&column.header(config), config.color_support)
+            })
             .collect()
     }
 
-    fn build_header(&self, config: &Config) -> Vec<String> {
-        let files: &str = if config.group_by_language {
-            "Files"
-        } else {
-            "Path"
-        };
-        self.selected_columns(
-            [
-                "Language", files, "Lines", "Words", "Chars", "Bytes", "Graph",
-            ]
-            .map(String::from),
-            config,
-        )
+    fn build_row(
+        &self,
+        file: &File,
+        results: &ScanResults,
+        config: &Config,
+        heatmap: Option<&Heatmap>,
+    ) -> Vec<String> {
+        config
+            .columns
+            .iter()
+            .map(|column| match column {
+                Column::Language => color(&file.language, &file.language.to_string(), config),
+                Column::Graph => self.build_visualization(file, results, config),
+                Column::Path if !config.group_by_language => {
+                    let path = column.value(file, results, config);
+                    match self.path_width {
+                        Some(width) => path::truncate_middle(&path, width),
+                        None => path,
+                    }
+                }
+                column => {
+                    let value = column.value(file, results, config);
+                    match heatmap.and_then(|heatmap| heatmap.color(file, column, config)) {
+                        Some(color) => color.paint(&value, config.color_support),
+                        None => self.number(&value, column, config),
+                    }
+                }
+            })
+            .collect()
     }
 
-    fn build_row(&self, file: &File, results: &ScanResults, config: &Config) -> String {
-        let mut cols = Vec::new();
+    /// Paints the value of a numeric column in the theme's number style
+    fn number(&self, value: &str, column: &Column, config: &Config) -> String {
+        match column {
+            Column::Language | Column::Graph => value.to_string(),
+            Column::Path if !config.group_by_language => value.to_string(),
+            _ => config.theme.numbers.paint(value, config.color_support),
+        }
+    }
 
-        if config.language {
-            let lang = if config.use_colors {
-                color(&file.language, &file.language.to_string())
-            } else {
-                file.language.to_string()
-            };
-            cols.push(lang);
+    fn build_others_row(
+        &self,
+        others: &File,
+        results: &ScanResults,
+        config: &Config,
+    ) -> Vec<String> {
+        config
+            .columns
+            .iter()
+            .map(|column| self.number(&column.others(others, results, config), column, config))
+            .collect()
+    }
+
+    /// Builds the bar of the row. When stacked and grouped by language, the bar is split into the shares of the
+    /// language's files (largest first).
+    fn build_visualization(&self, file: &File, results: &ScanResults, config: &Config) -> String {
+        let fraction = graph_fraction(file, results, config);
+        if config.graph_stacked && config.group_by_language {
+            let rgb = file.language.color();
+            let segments = file_shares(file, results, config)
+                .into_iter()
+                .map(|share| (rgb, share))
+                .collect::<Vec<_>>();
+            return self.build_stacked_bar(&segments, fraction, config);
+        }
+
+        let bar = self.build_bar(fraction, config);
+        config
+            .theme
+            .bar(file.language.color(), &bar, config.color_support)
+    }
+
+    /// Builds a bar filling the given fraction of the graph. Smooth bars end with an eighth-block.
+    fn build_bar(&self, fraction: f64, config: &Config) -> String {
+        let size = config.graph_size;
+        let fraction = clamp_fraction(fraction);
+
+        if config.graph_smooth {
+            let eighths = (fraction * size as f64 * 8.0).round() as usize;
+            let (full, partial) = (eighths / 8, eighths % 8);
+            let mut bar = repeat_to_width(&config.graph_fill, full);
+            if partial > 0 {
+                bar.push(PARTIAL_BLOCKS[partial - 1]);
+            }
+            let drawn = full + usize::from(partial > 0);
+            bar + &repeat_to_width(&config.graph_blank, size.saturating_sub(drawn))
+        } else {
+            let length = ((fraction * size as f64).round() as usize).min(size);
+            repeat_to_width(&config.graph_fill, length)
+                + &repeat_to_width(&config.graph_blank, size - length)
         }
+    }
 
-        if config.files {
-            let file = if config.group_by_language {
-                file.count.to_string()
+    /// Builds a bar filling the given fraction of the graph, split into a segment for each share (with the colour
+    /// it is painted in). The segments are told apart by their colours when they differ and the output is coloured,
+    /// and by their fills otherwise. The segment boundaries are rounded cumulatively so that the segments always
+    /// fill the whole bar.
+    fn build_stacked_bar(
+        &self,
+        segments: &[((u8, u8, u8), f64)],
+        fraction: f64,
+        config: &Config,
+    ) -> String {
+        let total = segments.iter().map(|(_, share)| share).sum::<f64>();
+        if !(total > 0.0 && total.is_finite()) {
+            return self.build_bar(0.0, config);
+        }
+
+        let by_color = config.theme.colors_bars(config.color_support)
+            && segments.windows(2).all(|pair| pair[0].0 != pair[1].0);
+        let size = config.graph_size;
+        let length = ((clamp_fraction(fraction) * size as f64).round() as usize).min(size);
+        let (mut cumulative, mut drawn, mut count) = (0.0, 0, 0);
+        let mut bar = String::new();
+        for (rgb, share) in segments {
+            cumulative += share;
+            let end = ((cumulative / total * length as f64).round() as usize).min(length);
+            if end <= drawn {
+                continue; // Too small a share to show
+            }
+            let fill = if by_color {
+                &config.graph_fill
             } else {
-                path::display(&file.path)
+                SEGMENT_FILLS[count % SEGMENT_FILLS.len()]
             };
-            cols.push(file);
+            let segment = repeat_to_width(fill, end - drawn);
+            bar.push_str(&config.theme.bar(*rgb, &segment, config.color_support));
+            (drawn, count) = (end, count + 1);
         }
+        bar + &repeat_to_width(&config.graph_blank, size - drawn)
+    }
 
-        if config.lines {
-            cols.push(file.lines.to_string());
+    /// Builds the bar of the total row, split into coloured segments for each language's share of the graph metric
+    fn build_stacked_visualization(&self, results: &ScanResults, config: &Config) -> String {
+        let segments = language_shares(results, config.graph_by)
+            .into_iter()
+            .map(|(language, share)| (language.color(), share))
+            .collect::<Vec<_>>();
+        self.build_stacked_bar(&segments, 1.0, config)
+    }
+
+    fn build_footer(&self, results: &ScanResults, config: &Config) -> Vec<String> {
+        config
+            .columns
+            .iter()
+            .map(|column| match column {
+                Column::Graph if config.graph_stacked => {
+                    self.build_stacked_visualization(results, config)
+                }
+                column => config
+                    .theme
+                    .footer
+                    .paint(&column.total(results, config), config.color_support),
+            })
+            .collect()
+    }
+
+    fn build_alignments(&self, config: &Config) -> Vec<Alignment> {
+        config
+            .columns
+            .iter()
+            .map(|column| column.alignment(config))
+            .collect()
+    }
+}
+
+/// Colours the numeric cells of the table on a gradient, relative to the other rows
+struct Heatmap<'a> {
+    scale: HeatScale,
+    results: &'a ScanResults,
+    /// The sorted values of each heat-mapped metric across the rows, to rank the cells against
+    values: HashMap<Metric, Vec<f64>>,
+}
+
+impl<'a> Heatmap<'a> {
+    fn new(scale: HeatScale, results: &'a ScanResults, config: &Config) -> Self {
+        let values = config
+            .columns
+            .iter()
+            .filter_map(|column| Self::metric(column, config))
+            .map(|metric| {
+                let mut values = results
+                    .files
+                    .iter()
+                    .filter_map(|file| file.value(metric))
+                    .collect::<Vec<_>>();
+                values.sort_by(f64::total_cmp);
+                (metric, values)
+            })
+            .collect();
+        Self {
+            scale,
+            results,
+            values,
         }
+    }
 
-        if config.words {
-            cols.push(file.words.to_string());
+    /// The metric that determines the heat of the column's cells (if it is a numeric column)
+    fn metric(column: &Column, config: &Config) -> Option<Metric> {
+        match column {
+            Column::Percent(metric) => Some(*metric),
+            Column::Path if config.group_by_language => Some(Metric::Files),
+            column => column.metric(),
         }
+    }
+
+    /// The colour of the file's cell in the given column
+    fn color(&self, file: &File, column: &Column, config: &Config) -> Option<Color> {
+        let metric = Self::metric(column, config)?;
+        let values = self.values.get(&metric)?;
+        let value = file.value(metric)?;
+        let fraction = match self.scale {
+            HeatScale::Max => {
+                let max = self.results.max.value(metric).or(values.last().copied())?;
+                value / max
+            }
+            HeatScale::Percentile => {
+                let rank = values.partition_point(|v| *v < value);
+                rank as f64 / values.len().saturating_sub(1).max(1) as f64
+            }
+        };
+        Some(Color::heat(fraction))
+    }
+}
+
+/// The fraction of the graph that the file's bar fills, relative to the largest file (linearly or logarithmically),
+/// or to the total (for metrics that can be summed). This may be `NaN` when there is nothing to scale against.
+pub(super) fn graph_fraction(file: &File, results: &ScanResults, config: &Config) -> f64 {
+    let value = file.value(config.graph_by).unwrap_or_default();
+    let max = match config.graph_scale {
+        GraphScale::Total => results.total.value(config.graph_by),
+        GraphScale::Max | GraphScale::Log => None,
+    }
+    .or(results.max.value(config.graph_by))
+    .unwrap_or_default();
+    match config.graph_scale {
+        GraphScale::Log => value.ln_1p() / max.ln_1p(),
+        _ => value / max,
+    }
+}
 
-        if config.chars {
-            cols.push(file.chars.to_string());
+/// Clamps the fraction of the graph to fill between empty and full (treating `NaN` as empty)
+fn clamp_fraction(fraction: f64) -> f64 {
+    if fraction.is_finite() {
+        fraction.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// The values of the graph metric for each file of the (grouped) row, largest first
+fn file_shares(file: &File, results: &ScanResults, config: &Config) -> Vec<f64> {
+    let values = results
+        .language_stats
+        .get(&file.language.to_string())
+        .and_then(|stats| stats.values(config.graph_by));
+    let mut shares = match values {
+        Some(values) => values.to_vec(),
+        None => file.value(config.graph_by).into_iter().collect(),
+    };
+    shares.sort_by(|a, b| b.total_cmp(a));
+    shares
+}
+
+/// A helper function to color a string according to the language's color (as permitted by the theme and terminal)
+pub(super) fn color(language: &Language, text: &str, config: &Config) -> String {
+    config
+        .theme
+        .language(language.color(), text, config.color_support)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{helpers::ansi::visible_width, output::ColorSupport};
+
+    fn results() -> ScanResults {
+        let mut results = ScanResults::default();
+        for path in ["src/lib/output/deeply/nested/module/file.rs", "main.rs"] {
+            let mut file = File::scan_reader(std::io::Cursor::new("one two\nthree")).unwrap();
+            file.path = path.into();
+            results.total.add(&file);
+            results.max.track(&file);
+            results.files.push(file);
         }
+        results
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let config = Config {
+            columns: vec![Column::Path, Column::Lines],
+            heatmap: Some(HeatScale::Max),
+            header: false,
+            footer: false,
+            ..Default::default()
+        };
+        let output = TableFormatter::default().format(&results(), &config);
+        let red = Color::heat(1.0).paint("2", config.color_support);
+        assert_eq!(output.matches(&red).count(), 2);
+        assert!(
+            output
+                .lines()
+                .all(|line| visible_width(line) == visible_width(output.lines().next().unwrap()))
+        );
+
+        let config = Config {
+            color_support: ColorSupport::None,
+            ..config
+        };
+        let output = TableFormatter::default().format(&results(), &config);
+        assert!(!output.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_smooth_bars() {
+        let config = Config {
+            graph_size: 4,
+            graph_fill: "█".into(),
+            graph_smooth: true,
+            ..Default::default()
+        };
+        let formatter = TableFormatter::default();
+        assert_eq!(formatter.build_bar(1.0, &config), "████");
+        assert_eq!(formatter.build_bar(0.5 + 3.0 / 32.0, &config), "██▍ ");
+        assert_eq!(formatter.build_bar(1.0 / 32.0, &config), "▏   ");
+        assert_eq!(formatter.build_bar(0.0, &config), "    ");
+        assert_eq!(formatter.build_bar(f64::NAN, &config), "    ");
+        assert_eq!(formatter.build_bar(2.0, &config), "████");
+    }
 
-        if config.bytes {
-            cols.push(file.bytes.to_string());
+    #[test]
+    fn test_log_scale() {
+        let mut results = results();
+        results.files[1] = File::scan_reader(std::io::Cursor::new("x")).unwrap();
+        let config = Config {
+            columns: vec![Column::Graph],
+            graph_by: Metric::Lines,
+            graph_size: 10,
+            graph_scale: GraphScale::Log,
+            header: false,
+            footer: false,
+            color_support: ColorSupport::None,
+            ..Default::default()
+        };
+        let output = TableFormatter::default().format(&results, &config);
+        let bars: Vec<usize> = output
+            .lines()
+            .map(|line| line.matches(config.graph_fill.as_str()).count())
+            .collect();
+        // ln(2) / ln(3) of the bar, rather than half of it with a linear scale
+        assert_eq!(bars, vec![10, 6]);
+    }
+
+    #[test]
+    fn test_stacked_total_bar() {
+        let mut results = results();
+        results.files[1].language = Language::from_extension("py");
+        let config = Config {
+            columns: vec![Column::Path, Column::Graph],
+            graph_size: 10,
+            graph_stacked: true,
+            header: false,
+            ..Default::default()
+        };
+        let output = TableFormatter::default().format(&results, &config);
+        let total = output.lines().last().unwrap();
+        assert_eq!(total.matches(config.graph_fill.as_str()).count(), 10);
+        for file in &results.files {
+            let segment = config.graph_fill.repeat(5);
+            let painted = config
+                .theme
+                .bar(file.language.color(), &segment, config.color_support);
+            assert!(total.contains(&painted));
         }
+    }
+
+    #[test]
+    fn test_stacked_grouped_bars() {
+        let mut results = results();
+        results.files[1].lines = 3 * results.files[0].lines;
+        let results = results.group_by_language();
+        let config = Config {
+            columns: vec![Column::Graph],
+            graph_by: Metric::Lines,
+            graph_size: 8,
+            graph_stacked: true,
+            group_by_language: true,
+            header: false,
+            footer: false,
+            color_support: ColorSupport::None,
+            ..Default::default()
+        };
+        let output = TableFormatter::default().format(&results, &config);
+        // Uncoloured segments are told apart by their fills, the largest file first
+        assert_eq!(output.trim(), "██████▓▓");
+    }
 
-        if config.graph {
-            let bar = self.build_visualization(file, results, config);
-            cols.push(bar);
+    #[test]
+    fn test_graph_of_empty_files() {
+        use crate::output::TableStyle;
+
+        let mut results = ScanResults::default();
+        for path in ["a.rs", "b.py"] {
+            let mut file = File::scan_reader(std::io::Cursor::new("")).unwrap();
+            file.path = path.into();
+            file.language = Language::from_path(path);
+            results.total.add(&file);
+            results.max.track(&file);
+            results.files.push(file);
+        }
+        for graph_scale in [GraphScale::Max, GraphScale::Total, GraphScale::Log] {
+            let config = Config {
+                columns: vec![Column::Path, Column::Graph],
+                graph_scale,
+                graph_stacked: true,
+                graph_size: 6,
+                table_style: TableStyle::AsciiGrid,
+                ..Default::default()
+            };
+            let output = TableFormatter::default().format(&results, &config);
+            assert!(!output.contains(config.graph_fill.as_str()));
+            let widths: Vec<usize> = output.lines().map(visible_width).collect();
+            assert!(widths.windows(2).all(|w| w[0] == w[1]));
         }
+    }
 
-        cols.join("\t") + "\n"
+    #[test]
+    fn test_wide_graph_fill() {
+        let config = Config {
+            graph_size: 5,
+            graph_fill: "日".into(),
+            graph_blank: "=>".into(),
+            ..Default::default()
+        };
+        let formatter = TableFormatter::default();
+        assert_eq!(formatter.build_bar(1.0, &config), "日日 ");
+        assert_eq!(formatter.build_bar(0.4, &config), "日=> ");
+        assert_eq!(formatter.build_bar(0.0, &config), "=>=> ");
     }
 
-    fn build_visualization(&self, file: &File, results: &ScanResults, config: &Config) -> String {
-        let fill = config.graph_fill.clone();
-        let blank = config.graph_blank.clone();
-        let max_length = config.graph_size;
+    mod properties {
+        use super::*;
+        use crate::output::{Format, TableStyle, display};
+        use proptest::prelude::*;
 
-        let bar_length = match config.graph_by {
-            Metric::Lines => {
-                (file.lines as f64 / results.max.lines as f64 * max_length as f64).round()
-            }
-            Metric::Words => {
-                (file.words as f64 / results.max.words as f64 * max_length as f64).round()
+        /// Scan results of up to eight files with arbitrary contents in a few languages
+        fn arbitrary_results() -> impl Strategy<Value = ScanResults> {
+            let file = (
+                prop::sample::select(vec!["a.rs", "b.py", "c.md", "d"]),
+                ".{0,200}",
+            );
+            prop::collection::vec(file, 0..8).prop_map(|files| {
+                let mut results = ScanResults::default();
+                for (i, (path, contents)) in files.into_iter().enumerate() {
+                    let mut file = File::scan_reader(std::io::Cursor::new(contents)).unwrap();
+                    file.path = format!("{i}/{path}").into();
+                    file.language = Language::from_path(path);
+                    results.total.add(&file);
+                    results.max.track(&file);
+                    results.files.push(file);
+                }
+                results
+            })
+        }
+
+        fn arbitrary_config() -> impl Strategy<Value = Config> {
+            (
+                prop::sample::select(vec![
+                    Metric::Files,
+                    Metric::Lines,
+                    Metric::Words,
+                    Metric::Bytes,
+                    Metric::MaxLineWidth,
+                ]),
+                prop::sample::select(vec![GraphScale::Max, GraphScale::Total, GraphScale::Log]),
+                "\\PC{0,3}",
+                "\\PC{0,3}",
+                0..40usize,
+                any::<(bool, bool, bool)>(),
+            )
+                .prop_map(
+                    |(graph_by, graph_scale, graph_fill, graph_blank, graph_size, flags)| {
+                        let (graph_smooth, graph_stacked, use_colors) = flags;
+                        Config {
+                            columns: vec![Column::Path, Column::Lines, Column::Graph],
+                            graph_by,
+                            graph_scale,
+                            graph_fill,
+                            graph_blank,
+                            graph_size,
+                            graph_smooth,
+                            graph_stacked,
+                            color_support: if use_colors {
+                                ColorSupport::TrueColor
+                            } else {
+                                ColorSupport::None
+                            },
+                            ..Default::default()
+                        }
+                    },
+                )
+        }
+
+        proptest! {
+            #[test]
+            fn bars_always_span_the_graph_size(fraction in any::<f64>(), config in arbitrary_config()) {
+                let bar = TableFormatter::default().build_bar(fraction, &config);
+                prop_assert_eq!(visible_width(&bar), config.graph_size);
             }
-            Metric::Chars => {
-                (file.chars as f64 / results.max.chars as f64 * max_length as f64).round()
+
+            #[test]
+            fn tables_render_with_aligned_rows(results in arbitrary_results(), config in arbitrary_config()) {
+                let config = Config { table_style: TableStyle::AsciiGrid, ..config };
+                let output = TableFormatter::default().format(&results, &config);
+                let widths: Vec<usize> = output.lines().map(visible_width).collect();
+                prop_assert!(widths.windows(2).all(|w| w[0] == w[1]), "{}", output);
             }
-            Metric::Bytes | _ => {
-                (file.bytes as f64 / results.max.bytes as f64 * max_length as f64).round()
+
+            #[test]
+            fn every_format_renders_without_panicking(
+                results in arbitrary_results(),
+                config in arbitrary_config(),
+                format in prop::sample::select(vec![Format::Table, Format::Plain, Format::JSON, Format::NDJSON, Format::YAML, Format::TOML, Format::XML, Format::TSV, Format::CSV, Format::HTML, Format::SVG, Format::OpenMetrics, Format::SARIF, Format::Checkstyle]),
+                grouped in any::<bool>(),
+                histogram in any::<bool>(),
+            ) {
+                let results = if grouped { results.group_by_language() } else { results };
+                let config = Config {
+                    format,
+                    histogram: histogram.then_some(config.graph_by),
+                    ..config
+                };
+                display(results, config);
             }
-        } as usize;
+        }
+    }
 
-        let bar = fill.repeat(bar_length) + &blank.repeat(max_length - bar_length);
-        let bar = if config.use_colors {
-            color(&file.language, &bar)
-        } else {
-            bar
+    fn render(width: usize) -> String {
+        let config = Config {
+            width: Some(width),
+            color_support: ColorSupport::None,
+            ..Default::default()
         };
-
-        bar
+        TableFormatter::default().format(&results(), &config)
     }
 
-    fn build_footer(&self, results: &ScanResults, config: &Config) -> Vec<String> {
-        self.selected_columns(
-            [
-                "Total".to_string(),
-                results.total.files.to_string(),
-                results.total.lines.to_string(),
-                results.total.words.to_string(),
-                results.total.chars.to_string(),
-                results.total.bytes.to_string(),
-            ],
-            config,
-        )
-    }
+    #[test]
+    fn test_fit_shrinks_graph_then_truncates_paths() {
+        let output = render(110);
+        assert!(output.lines().all(|line| visible_width(line) <= 110));
+        assert!(output.contains("Graph"));
+        assert!(output.contains("src/lib/output/deeply/nested/module/file.rs"));
 
-    fn build_alignments(&self, config: &Config) -> Vec<Alignment> {
-        let files_alignment = if config.group_by_language {
-            Alignment::Right
-        } else {
-            Alignment::Left
-        };
-        self.selected_columns(
-            [
-                Alignment::Right,
-                files_alignment,
-                Alignment::Right,
-                Alignment::Right,
-                Alignment::Right,
-                Alignment::Right,
-                Alignment::Left,
-            ],
-            config,
-        )
+        let output = render(90);
+        assert!(output.lines().all(|line| visible_width(line) <= 90));
+        assert!(output.contains("Graph"));
+        assert!(output.contains("src/…/nested/module/file.rs"));
     }
-}
 
-/// A helper function to color a string according to the language's color
-fn color(language: &Language, text: &str) -> String {
-    let (r, g, b) = language.color();
-    format!("\u{001b}[38;2;{};{};{}m{}\u{001b}[0m", r, g, b, text)
+    #[test]
+    fn test_fit_drops_columns_by_priority() {
+        let output = render(50);
+        assert!(output.lines().all(|line| visible_width(line) <= 50));
+        let header = output.lines().next().unwrap();
+        assert!(header.contains("Path") && header.contains("Bytes"));
+        assert!(!header.contains("Graph") && !header.contains("Chars"));
+    }
 }
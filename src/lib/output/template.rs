@@ -0,0 +1,313 @@
+use std::io::{self, Write};
+
+use crate::{
+    helpers::{ansi::visible_width, table::Alignment},
+    scanner::ScanResults,
+};
+
+use super::{Column, Config, Formatter, Human};
+
+/// A user-defined output format, with a header, a row and a footer section.
+///
+/// The row section is rendered for each file (and the "Others" row), while the header and footer sections
+/// are rendered once with the totals. Placeholders name a column in braces (e.g. `{path}`, `{lines}` or
+/// `{bytes%}`), optionally followed by modifiers: `:human` for human-readable numbers, and `:<N`, `:>N`
+/// or `:^N` to pad the value to `N` columns (aligned left, right or center). `{{` and `}}` are literal braces.
+///
+/// The sections start with the `{#header}`, `{#row}` and `{#footer}` markers. Any text before the first marker
+/// belongs to the header. Without any markers, the whole template is the row section.
+///
+/// ```text
+/// {#header}# {path} files{#row}{path:<30} {lines:>6}\n{#footer}{lines:human} lines in total
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Template {
+    header: Vec<Segment>,
+    row: Vec<Segment>,
+    footer: Vec<Segment>,
+}
+
+/// A piece of a [`Template`]
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Placeholder {
+        column: Column,
+        human: bool,
+        padding: Option<(Alignment, usize)>,
+    },
+}
+
+/// The sections of a [`Template`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Header,
+    Row,
+    Footer,
+}
+
+/// Renders the results with a [`Template`]
+#[derive(Debug)]
+pub struct TemplateFormatter<'a> {
+    template: &'a Template,
+}
+
+impl<'a> TemplateFormatter<'a> {
+    pub fn with(template: &'a Template) -> TemplateFormatter<'a> {
+        TemplateFormatter { template }
+    }
+}
+
+impl Formatter for TemplateFormatter<'_> {
+    fn write(
+        &self,
+        results: &ScanResults,
+        config: &Config,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        // The `:human` modifier formats the values as if human-readable numbers were enabled everywhere
+        let human = Config {
+            human: Some(Human::All),
+            ..config.clone()
+        };
+        let render = |segments: &[Segment], value: &dyn Fn(Column, &Config) -> String| {
+            let mut res = String::new();
+            for segment in segments {
+                match segment {
+                    Segment::Text(text) => res.push_str(text),
+                    Segment::Placeholder {
+                        column,
+                        human: humanize,
+                        padding,
+                    } => {
+                        let value = value(*column, if *humanize { &human } else { config });
+                        res.push_str(&pad(value, *padding));
+                    }
+                }
+            }
+            res
+        };
+
+        let total = |column: Column, config: &Config| column.total(results, config);
+        writer.write_all(render(&self.template.header, &total).as_bytes())?;
+        for file in &results.files {
+            let row = render(&self.template.row, &|column, config| {
+                column.value(file, results, config)
+            });
+            writer.write_all(row.as_bytes())?;
+        }
+        if let Some(others) = &results.others {
+            let row = render(&self.template.row, &|column, config| {
+                column.others(others, results, config)
+            });
+            writer.write_all(row.as_bytes())?;
+        }
+        writer.write_all(render(&self.template.footer, &total).as_bytes())
+    }
+}
+
+impl Template {
+    fn section(&mut self, section: Section) -> &mut Vec<Segment> {
+        match section {
+            Section::Header => &mut self.header,
+            Section::Row => &mut self.row,
+            Section::Footer => &mut self.footer,
+        }
+    }
+
+    /// Translates the escape sequences (`\n`, `\t` and `\\`) of a template given on the command line
+    pub fn unescape(template: &str) -> String {
+        let mut res = String::with_capacity(template.len());
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match (c, chars.clone().next()) {
+                ('\\', Some('n')) => res.push('\n'),
+                ('\\', Some('t')) => res.push('\t'),
+                ('\\', Some('\\')) => res.push('\\'),
+                _ => {
+                    res.push(c);
+                    continue;
+                }
+            }
+            chars.next();
+        }
+        res
+    }
+}
+
+/// Parses a placeholder (the text between the braces), e.g. `bytes:human:>10`
+fn parse_placeholder(placeholder: &str) -> Result<Segment, String> {
+    let mut parts = placeholder.split(':');
+    let name = parts.next().unwrap_or_default();
+    let column = name
+        .parse::<Column>()
+        .ok()
+        .filter(|column| column.is_data())
+        .ok_or_else(|| format!("Unknown placeholder in the template: {{{name}}}"))?;
+
+    let (mut human, mut padding) = (false, None);
+    for modifier in parts {
+        let alignment = match modifier.chars().next() {
+            Some('<') => Alignment::Left,
+            Some('>') => Alignment::Right,
+            Some('^') => Alignment::Center,
+            _ if modifier == "human" => {
+                human = true;
+                continue;
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown modifier in the template: {{{placeholder}}}"
+                ));
+            }
+        };
+        let width = modifier[1..]
+            .parse()
+            .map_err(|_| format!("Invalid width in the template: {{{placeholder}}}"))?;
+        padding = Some((alignment, width));
+    }
+
+    Ok(Segment::Placeholder {
+        column,
+        human,
+        padding,
+    })
+}
+
+/// Pads the value to the width with the alignment, if any
+fn pad(value: String, padding: Option<(Alignment, usize)>) -> String {
+    let Some((alignment, width)) = padding else {
+        return value;
+    };
+    let space = width.saturating_sub(visible_width(&value));
+    match alignment {
+        Alignment::Left => value + &" ".repeat(space),
+        Alignment::Right => " ".repeat(space) + &value,
+        Alignment::Center => " ".repeat(space / 2) + &value + &" ".repeat(space - space / 2),
+    }
+}
+
+impl std::str::FromStr for Template {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut template = Template::default();
+        let has_markers = ["{#header}", "{#row}", "{#footer}"]
+            .iter()
+            .any(|marker| s.contains(marker));
+        let mut section = if has_markers {
+            Section::Header
+        } else {
+            Section::Row
+        };
+
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => {
+                                return Err(format!(
+                                    "Unclosed placeholder in the template: {{{placeholder}"
+                                ));
+                            }
+                        }
+                    }
+
+                    let segments = template.section(section);
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    match placeholder.as_str() {
+                        "#header" => section = Section::Header,
+                        "#row" => section = Section::Row,
+                        "#footer" => section = Section::Footer,
+                        placeholder => segments.push(parse_placeholder(placeholder)?),
+                    }
+                }
+                '}' => {
+                    return Err(
+                        "Unmatched `}` in the template (use `}}` for a literal brace)".into(),
+                    );
+                }
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            template.section(section).push(Segment::Text(text));
+        }
+        Ok(template)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::scan_contents;
+
+    fn results() -> ScanResults {
+        scan_contents([
+            ("src/main.rs", "fn main() {}\n".to_string()),
+            ("README.md", "word ".repeat(400)),
+        ])
+    }
+
+    fn render(template: &str) -> String {
+        let template = template.parse::<Template>().unwrap();
+        TemplateFormatter::with(&template).format(&results(), &Config::default())
+    }
+
+    #[test]
+    fn test_row_template() {
+        assert_eq!(
+            render("{language}: {path} has {lines} lines\n"),
+            "Rust: src/main.rs has 1 lines\nMarkdown: README.md has 1 lines\n"
+        );
+        assert_eq!(render("{{{path}}} "), "{src/main.rs} {README.md} ");
+    }
+
+    #[test]
+    fn test_sections_and_modifiers() {
+        let output = render(
+            "{#header}# {path} files\n{#row}{path:<12}|{bytes:human:>9}|{bytes%:^7}\n{#footer}{bytes:human} in total\n",
+        );
+        let expected = "\
+# 2 files
+src/main.rs |     12 B| 0.6% \u{20}
+README.md   |  2.0 KiB| 99.4% 
+2.0 KiB in total
+";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!("{nope}".parse::<Template>().is_err());
+        assert!("{graph}".parse::<Template>().is_err());
+        assert!("{lines:wide}".parse::<Template>().is_err());
+        assert!("{lines:>x}".parse::<Template>().is_err());
+        assert!("{lines".parse::<Template>().is_err());
+        assert!("lines}".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(
+            Template::unescape(r"{path}\t{lines}\n\\n"),
+            "{path}\t{lines}\n\\n"
+        );
+    }
+}
@@ -0,0 +1,447 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+/// The colours a terminal can display, from none at all to full 24-bit colour
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    None,
+    /// The 16 standard ANSI colours
+    Ansi16,
+    /// The 256 colour xterm palette
+    Ansi256,
+    /// 24-bit RGB colours
+    #[default]
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Detects the colour support of the terminal from the `COLORTERM` and `TERM` environment variables
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").ok();
+        let term = std::env::var("TERM").ok();
+        Self::from_env(colorterm.as_deref(), term.as_deref())
+    }
+
+    /// Determines the colour support from the values of the `COLORTERM` and `TERM` environment variables
+    pub fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if let Some("truecolor" | "24bit") = colorterm {
+            return Self::TrueColor;
+        }
+        match term.unwrap_or_default() {
+            "dumb" => Self::None,
+            term if term.contains("truecolor") || term.contains("direct") => Self::TrueColor,
+            term if term.contains("256color") => Self::Ansi256,
+            // Windows terminals don't set `TERM`, but have supported truecolor for a long time
+            "" if cfg!(windows) => Self::TrueColor,
+            _ => Self::Ansi16,
+        }
+    }
+}
+
+/// A colour, as written in a theme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// One of the 16 standard ANSI colours (0-7 are the normal colours and 8-15 their bright variants)
+    Ansi(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// The RGB values of the 16 standard ANSI colours (as in xterm), used to find the closest match
+const ANSI_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The names of the standard ANSI colours, in palette order
+const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+impl Color {
+    /// The SGR parameters that set this colour as the foreground, downgraded to what the terminal supports
+    fn sgr(&self, support: ColorSupport) -> Option<String> {
+        match (*self, support) {
+            (_, ColorSupport::None) => None,
+            (Self::Ansi(n), _) if n < 8 => Some(format!("{}", 30 + n)),
+            (Self::Ansi(n), _) => Some(format!("{}", 90 + n - 8)),
+            (Self::Rgb(r, g, b), ColorSupport::TrueColor) => Some(format!("38;2;{r};{g};{b}")),
+            (Self::Rgb(r, g, b), ColorSupport::Ansi256) => {
+                Some(format!("38;5;{}", to_ansi256(r, g, b)))
+            }
+            (Self::Rgb(r, g, b), ColorSupport::Ansi16) => {
+                Self::Ansi(to_ansi16(r, g, b)).sgr(support)
+            }
+        }
+    }
+
+    /// Paints the text in this colour
+    pub fn paint(&self, text: &str, support: ColorSupport) -> String {
+        Style {
+            fg: Some(*self),
+            ..Default::default()
+        }
+        .paint(text, support)
+    }
+}
+
+impl Color {
+    /// The colour at the given fraction (between 0 and 1) of the heat-map gradient, from green through yellow to red
+    pub fn heat(fraction: f64) -> Self {
+        const STOPS: [(u8, u8, u8); 3] = [(60, 180, 75), (230, 200, 0), (230, 50, 40)];
+        let fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        let position = fraction * (STOPS.len() - 1) as f64;
+        let i = (position.floor() as usize).min(STOPS.len() - 2);
+        let t = position - i as f64;
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        let ((r1, g1, b1), (r2, g2, b2)) = (STOPS[i], STOPS[i + 1]);
+        Self::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Self::Rgb(r, g, b)
+    }
+}
+
+/// Maps an RGB colour onto the 6x6x6 colour cube (or the grayscale ramp) of the 256 colour palette
+fn to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    if r == g && g == b {
+        return match r {
+            0..8 => 16,
+            249.. => 231,
+            _ => 232 + ((r as u16 - 8) * 24 / 247) as u8,
+        };
+    }
+    let scale = |v: u8| (v as u16 * 5 + 127) / 255;
+    (16 + 36 * scale(r) + 6 * scale(g) + scale(b)) as u8
+}
+
+/// Finds the closest of the 16 standard ANSI colours to an RGB colour
+fn to_ansi16(r: u8, g: u8, b: u8) -> u8 {
+    let distance = |&(x, y, z): &(u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, x) + d(g, y) + d(b, z)
+    };
+    (0..16)
+        .min_by_key(|i| distance(&ANSI_PALETTE[*i]))
+        .unwrap_or_default() as u8
+}
+
+impl std::str::FromStr for Color {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Self::Rgb(r, g, b)),
+                _ => Err(format!("Invalid hex colour: {s}")),
+            };
+        }
+        let (name, offset) = match s.strip_prefix("bright-") {
+            Some(name) => (name, 8),
+            None => (s.as_str(), 0),
+        };
+        ANSI_NAMES
+            .iter()
+            .position(|n| *n == name)
+            .map(|i| Self::Ansi(i as u8 + offset))
+            .ok_or_else(|| format!("Invalid colour: {s}"))
+    }
+}
+
+/// How a piece of text is styled: its colour and attributes.
+///
+/// Written in themes as a space-separated list of attributes and a colour, e.g. `bold #ff8800` or `dim cyan`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    /// The escape sequence that applies the style (empty if the style doesn't change anything)
+    pub fn escape(&self, support: ColorSupport) -> String {
+        if support == ColorSupport::None {
+            return String::new();
+        }
+        let attributes = [
+            (self.bold, "1"),
+            (self.dim, "2"),
+            (self.italic, "3"),
+            (self.underline, "4"),
+        ];
+        let codes = attributes
+            .iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, code)| code.to_string())
+            .chain(self.fg.and_then(|fg| fg.sgr(support)))
+            .collect::<Vec<_>>();
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\u{001b}[{}m", codes.join(";"))
+        }
+    }
+
+    /// Paints the text in the style
+    pub fn paint(&self, text: &str, support: ColorSupport) -> String {
+        match self.escape(support) {
+            escape if escape.is_empty() || text.is_empty() => text.to_string(),
+            escape => format!("{escape}{text}\u{001b}[0m"),
+        }
+    }
+}
+
+impl std::str::FromStr for Style {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut style = Style::default();
+        for word in s.split_whitespace() {
+            match word.to_lowercase().as_str() {
+                "bold" => style.bold = true,
+                "dim" => style.dim = true,
+                "italic" => style.italic = true,
+                "underline" => style.underline = true,
+                "none" | "default" => {}
+                color => style.fg = Some(color.parse()?),
+            }
+        }
+        Ok(style)
+    }
+}
+
+impl<'de> Deserialize<'de> for Style {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The styles of the different parts of the output
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub header: Style,
+    /// The style of the footer rows (the totals and the statistics)
+    pub footer: Style,
+    /// The style of the borders and rules of the table
+    pub separator: Style,
+    pub numbers: Style,
+    /// The style of the graph bars. Without a colour, the bars take the colour of their language.
+    pub graph: Style,
+    /// Whether to colour the languages by their language colours
+    pub language_colors: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            header: Style::default(),
+            footer: Style::default(),
+            separator: Style::default(),
+            numbers: Style::default(),
+            graph: Style::default(),
+            language_colors: true,
+        }
+    }
+}
+
+impl Theme {
+    /// The names of the built-in themes
+    pub const BUILTIN: [&str; 3] = ["default", "vivid", "mono"];
+
+    /// Returns the built-in theme with the given name
+    pub fn builtin(name: &str) -> Option<Self> {
+        let style = |s: &str| s.parse().unwrap_or_default();
+        match name {
+            "default" => Some(Self::default()),
+            "vivid" => Some(Self {
+                header: style("bold bright-white"),
+                footer: style("bold"),
+                separator: style("dim"),
+                numbers: style("cyan"),
+                ..Default::default()
+            }),
+            "mono" => Some(Self {
+                header: style("bold"),
+                footer: style("bold"),
+                separator: style("dim"),
+                language_colors: false,
+                ..Default::default()
+            }),
+            _ => None,
+        }
+    }
+
+    /// Paints the text in the colour of the language, if the theme uses language colours
+    pub fn language(&self, rgb: (u8, u8, u8), text: &str, support: ColorSupport) -> String {
+        if self.language_colors {
+            Color::from(rgb).paint(text, support)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Whether the bars are painted in the colours of their languages, which tells the languages apart
+    pub fn colors_bars(&self, support: ColorSupport) -> bool {
+        support != ColorSupport::None && self.graph.fg.is_none() && self.language_colors
+    }
+
+    /// Paints a graph bar: in the graph style if it has a colour, otherwise in the colour of the language
+    pub fn bar(&self, rgb: (u8, u8, u8), text: &str, support: ColorSupport) -> String {
+        let style = match self.graph.fg {
+            Some(_) => self.graph,
+            None if self.language_colors => Style {
+                fg: Some(Color::from(rgb)),
+                ..self.graph
+            },
+            None => self.graph,
+        };
+        style.paint(text, support)
+    }
+}
+
+/// The theme settings of the configuration file
+///
+/// ```toml
+/// theme = "ocean"
+///
+/// [themes.ocean]
+/// header = "bold #7fdbff"
+/// separator = "dim blue"
+/// numbers = "cyan"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ThemeSettings {
+    /// The name of the theme to use
+    pub theme: Option<String>,
+    /// The user-defined themes
+    pub themes: BTreeMap<String, Theme>,
+}
+
+impl ThemeSettings {
+    /// Parses the theme settings from the contents of a TOML configuration file
+    pub fn from_toml(contents: &str) -> Result<Self, String> {
+        toml::from_str(contents).map_err(|e| e.to_string())
+    }
+
+    /// Resolves the theme with the given name (or the configured one), preferring the user-defined themes
+    pub fn resolve(&self, name: Option<&str>) -> Result<Theme, String> {
+        let name = name.or(self.theme.as_deref()).unwrap_or("default");
+        self.themes
+            .get(name)
+            .cloned()
+            .or_else(|| Theme::builtin(name))
+            .ok_or_else(|| format!("Unknown theme: {name}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_color_support() {
+        let detect = ColorSupport::from_env;
+        assert_eq!(
+            detect(Some("truecolor"), Some("xterm")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(detect(None, Some("xterm-256color")), ColorSupport::Ansi256);
+        assert_eq!(detect(None, Some("xterm")), ColorSupport::Ansi16);
+        assert_eq!(detect(None, Some("dumb")), ColorSupport::None);
+    }
+
+    #[test]
+    fn test_color_downgrade() {
+        let orange = Color::Rgb(255, 165, 0);
+        assert_eq!(
+            orange.paint("x", ColorSupport::TrueColor),
+            "\u{1b}[38;2;255;165;0mx\u{1b}[0m"
+        );
+        assert_eq!(
+            orange.paint("x", ColorSupport::Ansi256),
+            "\u{1b}[38;5;214mx\u{1b}[0m"
+        );
+        assert_eq!(
+            orange.paint("x", ColorSupport::Ansi16),
+            "\u{1b}[33mx\u{1b}[0m"
+        );
+        assert_eq!(orange.paint("x", ColorSupport::None), "x");
+        assert_eq!(to_ansi256(128, 128, 128), 243);
+    }
+
+    #[test]
+    fn test_heat_gradient() {
+        assert_eq!(Color::heat(0.0), Color::Rgb(60, 180, 75));
+        assert_eq!(Color::heat(0.5), Color::Rgb(230, 200, 0));
+        assert_eq!(Color::heat(1.0), Color::Rgb(230, 50, 40));
+        assert_eq!(Color::heat(f64::NAN), Color::heat(0.0));
+        assert_eq!(Color::heat(7.0), Color::heat(1.0));
+    }
+
+    #[test]
+    fn test_parse_style() {
+        let style: Style = "bold #FF8800 underline".parse().unwrap();
+        assert_eq!(style.fg, Some(Color::Rgb(255, 136, 0)));
+        assert!(style.bold && style.underline && !style.dim);
+        assert_eq!("bright-red".parse::<Color>(), Ok(Color::Ansi(9)));
+        assert!("#12345".parse::<Color>().is_err());
+        assert!("sparkly".parse::<Style>().is_err());
+        assert_eq!(
+            "dim cyan"
+                .parse::<Style>()
+                .unwrap()
+                .escape(ColorSupport::Ansi16),
+            "\u{1b}[2;36m"
+        );
+    }
+
+    #[test]
+    fn test_theme_settings() {
+        let settings = ThemeSettings::from_toml(
+            r##"
+            theme = "ocean"
+
+            [themes.ocean]
+            header = "bold #7fdbff"
+            language_colors = false
+            "##,
+        )
+        .unwrap();
+        let theme = settings.resolve(None).unwrap();
+        assert!(theme.header.bold);
+        assert!(!theme.language_colors);
+        assert_eq!(settings.resolve(Some("mono")).ok(), Theme::builtin("mono"));
+        assert!(settings.resolve(Some("missing")).is_err());
+        assert!(ThemeSettings::from_toml("[themes.bad]\nheader = \"sparkly\"").is_err());
+    }
+}
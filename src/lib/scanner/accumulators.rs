@@ -1,47 +1,241 @@
 use serde::Serialize;
 
+use crate::output::Metric;
+
 use super::File;
 
 // ------------
 // ACCUMULATORS
 // ------------
 
-/// Represents the accumulated total number of lines, words, chars and bytes in [`ScanResults`]
-#[derive(Debug, Default, Serialize)]
+/// Represents the accumulated total number of lines, words, chars, graphemes, width and bytes in [`ScanResults`]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct Totals {
     pub files: usize,
     pub lines: usize,
     pub words: usize,
     pub chars: usize,
+    pub graphemes: usize,
+    pub width: usize,
     pub bytes: u64,
 }
 
 impl Totals {
+    /// Returns the total for the given [`Metric`], or `None` if the metric can't be summed
+    pub fn value(&self, metric: Metric) -> Option<f64> {
+        match metric {
+            Metric::Files => Some(self.files as f64),
+            Metric::Lines => Some(self.lines as f64),
+            Metric::Words => Some(self.words as f64),
+            Metric::Chars => Some(self.chars as f64),
+            Metric::Graphemes => Some(self.graphemes as f64),
+            Metric::Width => Some(self.width as f64),
+            Metric::Bytes => Some(self.bytes as f64),
+            _ => None,
+        }
+    }
+
     /// Add the [`File`] statistics to the totals accumulator
     pub(crate) fn add(&mut self, file: &File) {
         self.files += file.count;
         self.lines += file.lines;
         self.words += file.words;
         self.chars += file.chars;
+        self.graphemes += file.graphemes;
+        self.width += file.width;
         self.bytes += file.bytes;
     }
 }
 
-/// Represents the max values for the number of lines, words, chars and bytes in [`ScanResults`]
-#[derive(Debug, Default, Serialize)]
+/// Represents the max values for the number of files, lines, words, chars, graphemes, width, bytes and line lengths in [`ScanResults`]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct Max {
+    /// The most files aggregated into a single row (i.e. in a language, when grouped)
+    pub files: usize,
     pub lines: usize,
     pub words: usize,
     pub chars: usize,
+    pub graphemes: usize,
+    pub width: usize,
     pub bytes: u64,
+    pub max_line_length: usize,
+    pub max_line_width: usize,
+    pub avg_line_length: f64,
 }
 
 impl Max {
-    /// Update the max values for the number of lines, words, chars and bytes by comparing it with a [`File`]
+    /// Returns the max value for the given [`Metric`], or `None` if the metric is not numeric
+    pub fn value(&self, metric: Metric) -> Option<f64> {
+        match metric {
+            Metric::Files => Some(self.files as f64),
+            Metric::Lines => Some(self.lines as f64),
+            Metric::Words => Some(self.words as f64),
+            Metric::Chars => Some(self.chars as f64),
+            Metric::Graphemes => Some(self.graphemes as f64),
+            Metric::Width => Some(self.width as f64),
+            Metric::Bytes => Some(self.bytes as f64),
+            Metric::MaxLineLength => Some(self.max_line_length as f64),
+            Metric::MaxLineWidth => Some(self.max_line_width as f64),
+            Metric::AvgLineLength => Some(self.avg_line_length),
+            _ => None,
+        }
+    }
+
+    /// Update the max values for the number of files, lines, words, chars, bytes and line lengths by comparing it with a [`File`]
     pub(crate) fn track(&mut self, file: &File) {
+        self.files = self.files.max(file.count);
         self.lines = self.lines.max(file.lines);
         self.words = self.words.max(file.words);
         self.chars = self.chars.max(file.chars);
+        self.graphemes = self.graphemes.max(file.graphemes);
+        self.width = self.width.max(file.width);
         self.bytes = self.bytes.max(file.bytes);
+        self.max_line_length = self.max_line_length.max(file.max_line_length);
+        self.max_line_width = self.max_line_width.max(file.max_line_width);
+        self.avg_line_length = self.avg_line_length.max(file.avg_line_length);
+    }
+}
+
+/// Collects the per-file values of each [numeric metric][Metric::NUMERIC] in [`ScanResults`] to compute distribution statistics
+#[derive(Debug, Default, Clone)]
+pub struct Stats {
+    /// The values of each numeric metric, in the same order as [`Metric::NUMERIC`]
+    values: [Vec<f64>; Metric::NUMERIC.len()],
+}
+
+impl Stats {
+    /// Record the [`File`] statistics in the distribution
+    pub(crate) fn track(&mut self, file: &File) {
+        for (values, metric) in self.values.iter_mut().zip(Metric::NUMERIC) {
+            values.push(file.value(metric).unwrap_or_default());
+        }
+    }
+
+    /// The values of the given metric for each file, or `None` if the metric is not numeric
+    pub fn values(&self, metric: Metric) -> Option<&[f64]> {
+        let index = Metric::NUMERIC.iter().position(|m| *m == metric)?;
+        Some(&self.values[index])
+    }
+
+    /// Summarizes the distribution of the given metric, or `None` if the metric is not numeric
+    pub fn summary(&self, metric: Metric) -> Option<Summary> {
+        self.values(metric).map(Summary::of)
+    }
+}
+
+impl Serialize for Stats {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(Metric::NUMERIC.len()))?;
+        for (values, metric) in self.values.iter().zip(Metric::NUMERIC) {
+            map.serialize_entry(&metric.key(), &Summary::of(values))?;
+        }
+        map.end()
+    }
+}
+
+/// The distribution statistics of a single metric across the files in [`ScanResults`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct Summary {
+    pub min: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub std_dev: f64,
+}
+
+impl Summary {
+    /// Computes the summary statistics of the given values. An empty set of values summarizes to all zeros.
+    fn of(values: &[f64]) -> Summary {
+        if values.is_empty() {
+            return Summary::default();
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / n;
+        let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+
+        Summary {
+            min: sorted[0],
+            mean,
+            median: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+            std_dev: variance.sqrt(),
+        }
+    }
+
+    /// Returns the statistics paired with their display labels
+    pub fn labelled(&self) -> [(&'static str, f64); 6] {
+        [
+            ("Min", self.min),
+            ("Mean", self.mean),
+            ("Median", self.median),
+            ("P90", self.p90),
+            ("P99", self.p99),
+            ("Std Dev", self.std_dev),
+        ]
+    }
+}
+
+/// Computes the `p`-th percentile of the sorted values, linearly interpolating between the closest ranks
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_of_empty_values() {
+        assert_eq!(Summary::of(&[]), Summary::default());
+    }
+
+    #[test]
+    fn test_summary_of_values() {
+        let summary = Summary::of(&[4.0, 1.0, 3.0, 2.0, 5.0]);
+        assert_eq!(summary.min, 1.0);
+        assert_eq!(summary.mean, 3.0);
+        assert_eq!(summary.median, 3.0);
+        assert_eq!(summary.p90, 4.6);
+        assert_eq!(summary.p99, 4.96);
+        assert_eq!(summary.std_dev, 2.0_f64.sqrt());
+    }
+
+    #[test]
+    fn test_stats_keys_match_the_json_output() {
+        let json = serde_json::to_value(Stats::default()).unwrap();
+        let keys = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert!(keys.contains(&"avg_line_length".to_string()));
+        assert!(keys.iter().all(|key| !key.contains('-')));
+    }
+
+    #[test]
+    fn test_max_tracks_the_file_count() {
+        let mut max = Max::default();
+        for count in [3, 7, 1] {
+            max.track(&File {
+                count,
+                ..File::default()
+            });
+        }
+        assert_eq!(max.value(Metric::Files), Some(7.0));
+    }
+
+    #[test]
+    fn test_percentile_of_single_value() {
+        assert_eq!(percentile(&[7.0], 99.0), 7.0);
     }
 }
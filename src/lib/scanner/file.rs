@@ -1,8 +1,10 @@
 use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 use std::io::BufRead;
 
-use crate::helpers::language::Language;
+use crate::{helpers::language::Language, output::Metric};
 
 /// Represents a scanned file and its computed metrics.
 ///
@@ -10,9 +12,14 @@ use crate::helpers::language::Language;
 /// - `lines`: The number of lines (as determined by [`BufRead::lines`]).
 /// - `words`: The number of words (splitting each line on whitespace).
 /// - `chars`: The total number of Unicode characters (excluding newline characters).
+/// - `graphemes`: The total number of user-perceived characters, i.e. extended grapheme clusters (excluding newline characters).
+/// - `width`: The total East-Asian display width of the text, i.e. the number of terminal columns it occupies.
 /// - `bytes`: The total number of bytes (queried from file [`metadata`][std::fs::Metadata]).
+/// - `max_line_length`: The length of the longest line in Unicode characters.
+/// - `max_line_width`: The display width of the widest line in terminal columns.
+/// - `avg_line_length`: The average number of Unicode characters per line.
 /// - `language`: The [`language`][Language] detected from the file extension.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct File {
     /// The path to the file
     pub path: std::path::PathBuf,
@@ -22,19 +29,43 @@ pub struct File {
     pub words: usize,
     /// The number of unicode characters in the file
     pub chars: usize,
+    /// The number of grapheme clusters (user-perceived characters) in the file
+    pub graphemes: usize,
+    /// The display width of the file's contents in terminal columns
+    pub width: usize,
     /// The size of the file in bytes
     pub bytes: u64,
+    /// The number of unicode characters in the longest line
+    pub max_line_length: usize,
+    /// The display width of the widest line in terminal columns
+    pub max_line_width: usize,
+    /// The average number of unicode characters per line
+    pub avg_line_length: f64,
+    /// The lines that exceed the line-length limit, if one was requested when scanning
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub long_lines: Vec<LongLine>,
     /// The language used in this file
     pub language: Language,
     /// The number of files this entry represents. Usually 1 unless aggregated under a language.
     pub count: usize,
 }
 
+/// Represents a line that exceeds the configured line-length limit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LongLine {
+    /// The (1-based) line number
+    pub line: usize,
+    /// The number of unicode characters in the line
+    pub length: usize,
+    /// The display width of the line in terminal columns
+    pub width: usize,
+}
+
 impl File {
     /// Scans the file at the given [`path`][std::path::Path] and computes various [metrics][File].
     ///
     /// This function opens the file, reads it once line by line,
-    /// and computes the number of `lines`, `words`, and `characters`. The `byte count` is obtained
+    /// and computes the number of `lines`, `words`, `characters`, `graphemes` and the display `width`. The `byte count` is obtained
     /// from the file [`metadata`][std::fs::Metadata]. The file’s [`language`][Language] is determined from its extension.
     ///
     /// # Arguments
@@ -45,6 +76,15 @@ impl File {
     ///
     /// Returns an [`std::io::Error`] if opening the file, reading from it, or obtaining its metadata fails.
     pub fn scan<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<File> {
+        Self::scan_with_limit(path, None)
+    }
+
+    /// Scans the file at the given [`path`][std::path::Path] like [`File::scan`], additionally recording
+    /// every line whose length (in unicode characters) exceeds `max_line_length` in [`File::long_lines`].
+    pub fn scan_with_limit<P: AsRef<std::path::Path>>(
+        path: P,
+        max_line_length: Option<usize>,
+    ) -> std::io::Result<File> {
         // Open file
         let file = std::fs::File::open(&path)?;
 
@@ -54,37 +94,22 @@ impl File {
         // Create a buffered reader
         let reader = std::io::BufReader::new(file);
 
-        // Setup the counters
-        let mut lines = 0;
-        let mut words = 0;
-        let mut chars = 0;
-
         // Process each line...
+        let mut counter = Counter::new(max_line_length);
         for line in reader.lines() {
-            let line = line?; // Propagate error up, if any
-            lines += 1; // Increment the line count
-            words += line.split_whitespace().count(); // Increment the word count
-            chars += line.chars().count(); // Increment the characters count
+            counter.count(&line?); // Propagate error up, if any
         }
 
         // Determine the language from the file extension
         let path = path.as_ref().to_path_buf();
         let language = Language::from_path(&path);
 
-        Ok(File {
-            path,
-            lines,
-            words,
-            chars,
-            bytes,
-            language,
-            count: 1,
-        })
+        Ok(counter.finish(path, bytes, language))
     }
 
     /// Scans the given [reader][BufRead]  and computes various [metrics][File]
     ///
-    /// The function reads the reader line-by-line, and computes the number of `lines`, `words`, `characters` and `bytes`.
+    /// The function reads the reader line-by-line, and computes the number of `lines`, `words`, `characters`, `graphemes`, `width` and `bytes`.
     /// The language, currently, is always assumed to be [Text][Language::Text].
     ///
     /// # Arguments
@@ -95,46 +120,153 @@ impl File {
     ///
     /// Returns an error if we fail to [read][BufRead::lines] a line from the [reader][BufRead]
     pub fn scan_reader<R: BufRead>(reader: R) -> std::io::Result<File> {
-        // Setup the counter
-        let mut lines = 0;
-        let mut words = 0;
-        let mut chars = 0;
+        Self::scan_reader_with_limit(reader, None)
+    }
+
+    /// Scans the given [reader][BufRead] like [`File::scan_reader`], additionally recording
+    /// every line whose length (in unicode characters) exceeds `max_line_length` in [`File::long_lines`].
+    pub fn scan_reader_with_limit<R: BufRead>(
+        reader: R,
+        max_line_length: Option<usize>,
+    ) -> std::io::Result<File> {
+        let mut counter = Counter::new(max_line_length);
         let mut bytes = 0;
 
         // Process each line...
         for line in reader.lines() {
             let line = line?; // Propagate error up, if any
-            lines += 1; // Increment the line count
-            words += line.split_whitespace().count(); // Increment the word count
-            chars += line.chars().count(); // Increment the characters count
+            counter.count(&line);
             bytes += line.len() as u64; // Increment the number of bytes by adding the length
         }
-        bytes += (lines as u64) - 1; // Adjust the number of bytes to account for the missing \n (consumed by reader.lines()) and remove trailing \n
+        bytes += (counter.lines as u64).saturating_sub(1); // Adjust the number of bytes to account for the missing \n (consumed by reader.lines()) and remove trailing \n
 
         let path = std::path::Path::new("STDIN").to_path_buf();
         let language = Language::Text; // Default to plain-text for now.
 
-        Ok(File {
+        Ok(counter.finish(path, bytes, language))
+    }
+}
+
+impl File {
+    /// An empty aggregate for the files that were [filtered][super::ScanResults::filter] out of the results,
+    /// with `Others` as its path and an unknown language
+    pub fn others() -> File {
+        File {
+            path: "Others".into(),
+            language: Language::Unknown(String::new()),
+            ..File::default()
+        }
+    }
+
+    /// Returns the value of the given [`Metric`] for this file, or `None` for non-numeric metrics (i.e. [`Metric::Language`])
+    pub fn value(&self, metric: Metric) -> Option<f64> {
+        match metric {
+            Metric::Language | Metric::Path => None,
+            Metric::Files => Some(self.count as f64),
+            Metric::Lines => Some(self.lines as f64),
+            Metric::Words => Some(self.words as f64),
+            Metric::Chars => Some(self.chars as f64),
+            Metric::Graphemes => Some(self.graphemes as f64),
+            Metric::Width => Some(self.width as f64),
+            Metric::Bytes => Some(self.bytes as f64),
+            Metric::MaxLineLength => Some(self.max_line_length as f64),
+            Metric::MaxLineWidth => Some(self.max_line_width as f64),
+            Metric::AvgLineLength => Some(self.avg_line_length),
+        }
+    }
+}
+
+/// Accumulates the per-line metrics while a [`File`] is being read
+#[derive(Default)]
+struct Counter {
+    lines: usize,
+    words: usize,
+    chars: usize,
+    graphemes: usize,
+    width: usize,
+    max_line_length: usize,
+    max_line_width: usize,
+    /// The line-length limit above which lines are recorded in `long_lines`
+    limit: Option<usize>,
+    long_lines: Vec<LongLine>,
+}
+
+impl Counter {
+    fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    /// Accumulate the metrics for a single line (without its line terminator)
+    fn count(&mut self, line: &str) {
+        let length = line.chars().count();
+        let width = line.width();
+
+        self.lines += 1; // Increment the line count
+        self.words += line.split_whitespace().count(); // Increment the word count
+        self.chars += length; // Increment the characters count
+        self.graphemes += line.graphemes(true).count(); // Increment the grapheme cluster count
+        self.width += width; // Increment the display width
+        self.max_line_length = self.max_line_length.max(length);
+        self.max_line_width = self.max_line_width.max(width);
+
+        if self.limit.is_some_and(|limit| length > limit) {
+            self.long_lines.push(LongLine {
+                line: self.lines,
+                length,
+                width,
+            });
+        }
+    }
+
+    /// Consume the counter and build the [`File`]
+    fn finish(self, path: std::path::PathBuf, bytes: u64, language: Language) -> File {
+        File {
             path,
-            lines,
-            words,
-            chars,
+            lines: self.lines,
+            words: self.words,
+            chars: self.chars,
+            graphemes: self.graphemes,
+            width: self.width,
             bytes,
+            max_line_length: self.max_line_length,
+            max_line_width: self.max_line_width,
+            avg_line_length: average(self.chars, self.lines),
+            long_lines: self.long_lines,
             language,
             count: 1,
-        })
+        }
+    }
+}
+
+/// Computes the average line length, treating an empty file as having an average of zero
+fn average(chars: usize, lines: usize) -> f64 {
+    if lines == 0 {
+        0.0
+    } else {
+        chars as f64 / lines as f64
     }
 }
 
 impl std::ops::Add for File {
     type Output = File;
     fn add(self, rhs: Self) -> Self::Output {
+        let lines = self.lines + rhs.lines;
+        let chars = self.chars + rhs.chars;
         Self::Output {
             path: rhs.path.clone(),
-            lines: self.lines + rhs.lines,
+            lines,
             words: self.words + rhs.words,
-            chars: self.chars + rhs.words,
+            chars,
+            graphemes: self.graphemes + rhs.graphemes,
+            width: self.width + rhs.width,
             bytes: self.bytes + rhs.bytes,
+            max_line_length: self.max_line_length.max(rhs.max_line_length),
+            max_line_width: self.max_line_width.max(rhs.max_line_width),
+            avg_line_length: average(chars, lines),
+            long_lines: Vec::new(), // Line numbers are meaningless once files are aggregated
             language: rhs.language.clone(),
             count: self.count + rhs.count,
         }
@@ -206,4 +338,51 @@ mod tests {
         assert_eq!(metrics.bytes, contents.len() as u64, "Byte count mismatch");
         assert_eq!(metrics.language, Language::Text, "Language mismatch");
     }
+
+    #[test]
+    fn test_scan_reader_unicode_metrics() {
+        // "e" followed by a combining acute accent is two chars but a single grapheme.
+        // The family emoji is five chars (joined by ZWJs) but a single grapheme.
+        // The CJK ideographs are one char and one grapheme each, but two columns wide.
+        let contents = "cafe\u{0301}\n👨\u{200D}👩\u{200D}👧\n日本語";
+        let reader = std::io::Cursor::new(contents);
+
+        let metrics = File::scan_reader(reader).expect("Failed to scan reader");
+
+        assert_eq!(metrics.chars, 5 + 5 + 3, "Character count mismatch");
+        assert_eq!(metrics.graphemes, 4 + 1 + 3, "Grapheme count mismatch");
+        assert_eq!(metrics.width, 4 + 2 + 6, "Width count mismatch");
+    }
+
+    #[test]
+    fn test_scan_reader_line_length_metrics() {
+        let contents = "short\na much longer line\n日本語";
+        let reader = std::io::Cursor::new(contents);
+
+        let metrics = File::scan_reader_with_limit(reader, Some(5)).expect("Failed to scan reader");
+
+        assert_eq!(metrics.max_line_length, 18, "Max line length mismatch");
+        assert_eq!(metrics.max_line_width, 18, "Max line width mismatch");
+        assert_eq!(
+            metrics.avg_line_length,
+            26.0 / 3.0,
+            "Avg line length mismatch"
+        );
+        assert_eq!(
+            metrics.long_lines,
+            vec![LongLine {
+                line: 2,
+                length: 18,
+                width: 18
+            }],
+            "Long lines mismatch"
+        );
+    }
+
+    #[test]
+    fn test_scan_reader_without_limit_records_no_long_lines() {
+        let reader = std::io::Cursor::new("a very long line indeed");
+        let metrics = File::scan_reader(reader).expect("Failed to scan reader");
+        assert!(metrics.long_lines.is_empty());
+    }
 }
@@ -1,13 +1,17 @@
 use globset::GlobSet;
 
-use crate::helpers;
+use std::path::PathBuf;
+
+use crate::{Error, Result, helpers};
 
 mod accumulators;
-use accumulators::{Max, Totals};
+pub use accumulators::{Max, Stats, Summary, Totals};
 mod file;
-pub use file::File;
+pub use file::{File, LongLine};
 mod results;
-pub use results::{ScanResults, SortOrder};
+pub use results::{ScanResults, SortKey, SortOrder};
+mod policy;
+pub use policy::{Breach, Policy};
 
 // -------
 // SCANNER
@@ -23,6 +27,8 @@ pub struct Scanner {
     ignore_hidden: bool,
     /// Exclude files that match the pattern from the scan
     exclude: Option<GlobSet>,
+    /// Record the lines that are longer than this limit (in unicode characters)
+    max_line_length: Option<usize>,
 }
 
 impl Scanner {
@@ -57,64 +63,57 @@ impl Scanner {
         self
     }
 
-    /// Scan the given [`paths`][std::path::Path] and record [file information][File] such as the line, word, character, and byte counts for each file.
-    pub fn scan<P: AsRef<std::path::Path>>(&self, paths: &[P]) -> std::io::Result<ScanResults> {
-        // A vector to collect the files
-        let mut files = Vec::new();
-
-        // Accumulators
-        let mut total = Totals::default();
-        let mut max = Max::default();
-
-        for path in paths {
-            match path {
-                // If the path is -, then scan STDIN
-                p if p.as_ref().to_str() == Some("-") => {
-                    let reader = std::io::BufReader::new(std::io::stdin());
-                    if let Ok(results) = File::scan_reader(reader) {
-                        total.add(&results);
-                        max.track(&results);
-                        files.push(results);
-                    }
-                }
-
-                // If path points to a file, then parse the file, accumulate stats, and add to the collection
-                p if p.as_ref().is_file() => {
-                    if let Ok(file) = File::scan(path) {
-                        total.add(&file);
-                        max.track(&file);
-                        files.push(file);
-                    }
-                }
+    /// Record the lines that exceed the given length (in unicode characters) in [`File::long_lines`]
+    pub fn max_line_length(&mut self, limit: Option<usize>) -> &mut Self {
+        self.max_line_length = limit;
+        self
+    }
 
-                // If path points to a directory, then walk the directory accumulating stats, and add them to the collection
-                p if p.as_ref().is_dir() => {
-                    // Build a directory walker that respects `.gitignore` and other hidden files
-                    let walker = self.configure_walker(path);
-
-                    // Iterate over all the entries
-                    for result in walker {
-                        match result {
-                            Ok(entry) if entry.path().is_file() => {
-                                // Parse the file, accumulate stats, and add it to the collection
-                                if let Ok(file) = File::scan(entry.path()) {
-                                    total.add(&file);
-                                    max.track(&file);
-                                    files.push(file);
-                                }
-                            }
-
-                            Ok(_) => {} // Ignore directories and symlinks
-                            Err(e) => eprintln!("Error: {}", e), // Report errors
-                        }
-                    }
-                }
+    /// Scan the given [`paths`][std::path::Path] and record [file information][File] such as the line, word, character, and byte counts for each file.
+    ///
+    /// The paths that can't be read or walked are skipped, and returned alongside the results.
+    /// Use [`Scanner::files`] to handle the errors as they happen.
+    ///
+    /// ```
+    /// let (results, errors) = tali::scanner::Scanner::new().scan(&["src", "missing.rs"]);
+    /// assert!(results.total.lines > 0);
+    /// assert_eq!(errors.len(), 1);
+    /// ```
+    pub fn scan<P: AsRef<std::path::Path>>(&self, paths: &[P]) -> (ScanResults, Vec<Error>) {
+        let mut errors = Vec::new();
+        let results = self.files(paths).skip_errors(|e| errors.push(e)).collect();
+        (results, errors)
+    }
 
-                _ => {} // Ignore all other cases
-            }
+    /// Scan the given [`paths`][std::path::Path] lazily, yielding the [file information][File] of each file
+    /// as soon as it has been read (or the error that prevented it). A path of `-` reads STDIN.
+    ///
+    /// ```
+    /// use tali::scanner::{ScanResults, Scanner};
+    ///
+    /// let mut scanner = Scanner::new();
+    /// scanner.scan_depth(Some(1));
+    /// let results = scanner
+    ///     .files(&["src"])
+    ///     .collect::<Result<ScanResults, _>>()?;
+    /// assert!(results.files.iter().any(|file| file.path.ends_with("main.rs")));
+    /// # Ok::<(), tali::Error>(())
+    /// ```
+    pub fn files<P: AsRef<std::path::Path>>(&self, paths: &[P]) -> Files<'_> {
+        Files {
+            scanner: self,
+            paths: paths
+                .iter()
+                .map(|path| path.as_ref().to_path_buf())
+                .collect::<Vec<_>>()
+                .into_iter(),
+            walker: None,
         }
+    }
 
-        Ok(ScanResults { files, total, max })
+    /// Scan a single file
+    fn scan_file(&self, path: &std::path::Path) -> Result<File> {
+        File::scan_with_limit(path, self.max_line_length).map_err(|e| Error::io(path, e))
     }
 
     /// Setup the walker with the provided configuration
@@ -131,13 +130,78 @@ impl Scanner {
         if let Some(exclude) = self.exclude.clone() {
             walker.filter_entry(move |entry| {
                 let path = helpers::path::display(entry.path());
-                if exclude.is_match(path) {
-                    return false;
-                }
-                return true;
+                !exclude.is_match(path)
             });
         }
 
         walker.build()
     }
 }
+
+/// The iterator over the files of a scan, returned by [`Scanner::files`]
+pub struct Files<'a> {
+    scanner: &'a Scanner,
+    /// The paths that are left to scan
+    paths: std::vec::IntoIter<PathBuf>,
+    /// The walker of the directory being scanned, if any
+    walker: Option<ignore::Walk>,
+}
+
+impl<'a> Files<'a> {
+    /// Skips the paths that can't be read or walked (like [`Scanner::scan`]), handing each error to `skipped`
+    ///
+    /// ```
+    /// use tali::scanner::Scanner;
+    ///
+    /// let scanner = Scanner::new();
+    /// let files = scanner.files(&["src"]).skip_errors(|e| eprintln!("Error: {e}"));
+    /// assert!(files.count() > 0);
+    /// ```
+    pub fn skip_errors(self, mut skipped: impl FnMut(Error)) -> impl Iterator<Item = File> {
+        self.filter_map(move |file| file.map_err(&mut skipped).ok())
+    }
+}
+
+impl Iterator for Files<'_> {
+    type Item = Result<File>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Continue with the entries of the directory being walked
+            if let Some(walker) = &mut self.walker {
+                match walker.next() {
+                    Some(Ok(entry)) if entry.path().is_file() => {
+                        return Some(self.scanner.scan_file(entry.path()));
+                    }
+                    Some(Ok(_)) => continue, // Ignore directories and symlinks
+                    Some(Err(e)) => return Some(Err(Error::Walk(e))),
+                    None => self.walker = None,
+                }
+            }
+
+            let path = self.paths.next()?;
+            match path {
+                // If the path is -, then scan STDIN
+                p if p.to_str() == Some("-") => {
+                    let reader = std::io::BufReader::new(std::io::stdin());
+                    let file = File::scan_reader_with_limit(reader, self.scanner.max_line_length);
+                    return Some(file.map_err(|e| Error::io("STDIN", e)));
+                }
+
+                // If path points to a file, then parse the file
+                p if p.is_file() => return Some(self.scanner.scan_file(&p)),
+
+                // If path points to a directory, then walk the directory (respecting `.gitignore` and other hidden files)
+                p if p.is_dir() => self.walker = Some(self.scanner.configure_walker(p)),
+
+                p => {
+                    let error = std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "No such file or directory",
+                    );
+                    return Some(Err(Error::io(p, error)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{helpers::path, output::Metric};
+
+use super::{File, ScanResults, Totals};
+
+// ------
+// POLICY
+// ------
+
+/// A rule the scan results must not break, e.g. to fail a CI pipeline when a file grows too large.
+///
+/// Parsed from specs like `lines>1000` (checked against each file), `total.lines>500000` (checked against the
+/// totals) or `count>0` (checked against the number of files). A spec can be preceded by a `language=NAME`
+/// selector to only consider the files of that language, e.g. `language=JavaScript count>0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    /// Only consider the files of this language (case-insensitive), if any
+    language: Option<String>,
+    scope: Scope,
+    metric: Metric,
+    comparison: Comparison,
+    threshold: f64,
+}
+
+/// What a [`Policy`] is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// Each file on its own
+    File,
+    /// The sum over the files
+    Total,
+    /// The number of files
+    Count,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    /// The operators and their comparisons (the two-character operators take precedence over their prefixes)
+    const OPERATORS: [(&'static str, Comparison); 7] = [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        ("!=", Comparison::NotEqual),
+        ("==", Comparison::Equal),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+    ];
+
+    fn holds(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Equal => value == threshold,
+            Comparison::NotEqual => value != threshold,
+        }
+    }
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = match self {
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "!=",
+        };
+        write!(f, "{operator}")
+    }
+}
+
+/// A [`Policy`] that the scan results broke, with the value that broke it and the offending files
+#[derive(Debug)]
+pub struct Breach<'a> {
+    pub policy: &'a Policy,
+    /// The total or the number of files that broke the policy (not set for the policies checked against each file)
+    pub value: Option<f64>,
+    /// The files that broke the policy (or that were counted, for the `count` policies)
+    pub files: Vec<&'a File>,
+}
+
+impl Policy {
+    /// Checks the policy against the files of the results, returning the breach if it doesn't hold
+    pub fn evaluate<'a>(&'a self, results: &'a ScanResults) -> Option<Breach<'a>> {
+        let files = results.files.iter().filter(|file| {
+            self.language
+                .as_ref()
+                .is_none_or(|language| file.language.to_string().eq_ignore_ascii_case(language))
+        });
+
+        let breach = |value: Option<f64>, files: Vec<&'a File>| Breach {
+            policy: self,
+            value,
+            files,
+        };
+        match self.scope {
+            Scope::File => {
+                let offenders = files
+                    .filter(|file| {
+                        file.value(self.metric)
+                            .is_some_and(|value| self.comparison.holds(value, self.threshold))
+                    })
+                    .collect::<Vec<_>>();
+                (!offenders.is_empty()).then(|| breach(None, offenders))
+            }
+            Scope::Total => {
+                let mut total = Totals::default();
+                files.for_each(|file| total.add(file));
+                let value = total.value(self.metric)?;
+                self.comparison
+                    .holds(value, self.threshold)
+                    .then(|| breach(Some(value), Vec::new()))
+            }
+            Scope::Count => {
+                let files = files.collect::<Vec<_>>();
+                let value = files.iter().map(|file| file.count).sum::<usize>() as f64;
+                self.comparison
+                    .holds(value, self.threshold)
+                    .then(|| breach(Some(value), files))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Policy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The condition is the last word, preceded by the (optional) language selector
+        let s = s.trim();
+        let (selector, condition) = match s.rsplit_once(char::is_whitespace) {
+            Some((selector, condition)) => (Some(selector.trim()), condition),
+            None => (None, s),
+        };
+        let language = match selector {
+            Some(selector) => {
+                let (key, language) = selector
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid policy selector: {selector}"))?;
+                match key.trim().to_lowercase().as_str() {
+                    "language" | "lang" => Some(language.trim().to_string()),
+                    key => return Err(format!("Invalid policy selector: {key}")),
+                }
+            }
+            None => None,
+        };
+
+        let (position, operator, comparison) = Comparison::OPERATORS
+            .iter()
+            .filter_map(|(operator, comparison)| {
+                condition
+                    .find(operator)
+                    .map(|position| (position, *operator, *comparison))
+            })
+            .min_by_key(|(position, operator, _)| (*position, std::cmp::Reverse(operator.len())))
+            .ok_or_else(|| format!("Missing comparison in the policy: {condition}"))?;
+        let (name, threshold) = (
+            &condition[..position],
+            &condition[position + operator.len()..],
+        );
+        let threshold = threshold
+            .parse::<f64>()
+            .map_err(|_| format!("Invalid threshold in the policy: {threshold}"))?;
+
+        let (scope, metric) = match name.strip_prefix("total.") {
+            Some(name) => (Scope::Total, name.parse::<Metric>()?),
+            None => match name.parse::<Metric>()? {
+                Metric::Files => (Scope::Count, Metric::Files),
+                metric => (Scope::File, metric),
+            },
+        };
+        if !metric.is_numeric() && metric != Metric::Files {
+            return Err(format!("The {metric} can't be compared"));
+        }
+        if scope == Scope::Total && !metric.is_additive() {
+            return Err(format!("The {metric} can't be summed"));
+        }
+
+        Ok(Policy {
+            language,
+            scope,
+            metric,
+            comparison,
+            threshold,
+        })
+    }
+}
+
+impl std::fmt::Display for Policy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(language) = &self.language {
+            write!(f, "language={language} ")?;
+        }
+        match self.scope {
+            Scope::File => write!(f, "{}", self.metric)?,
+            Scope::Total => write!(f, "total.{}", self.metric)?,
+            Scope::Count => write!(f, "count")?,
+        }
+        write!(f, "{}{}", self.comparison, self.threshold)
+    }
+}
+
+/// Describes the breach for the error output: the policy (and the value that broke it), followed by the files
+impl std::fmt::Display for Breach<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value {
+            Some(value) => writeln!(f, "Policy failed: {} (got {value})", self.policy)?,
+            None => writeln!(f, "Policy failed: {}", self.policy)?,
+        }
+        for file in &self.files {
+            match self.policy.scope {
+                Scope::File => {
+                    let value = file.value(self.policy.metric).unwrap_or_default();
+                    writeln!(
+                        f,
+                        "  {}: {value} {}",
+                        path::display(&file.path),
+                        self.policy.metric
+                    )?
+                }
+                _ => writeln!(f, "  {}", path::display(&file.path))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::scan_contents;
+
+    fn results() -> ScanResults {
+        let files = [("big.rs", 30), ("small.rs", 2), ("app.js", 5)];
+        scan_contents(files.map(|(path, lines)| (path, "x\n".repeat(lines))))
+    }
+
+    fn evaluate(policy: &str) -> Option<(Option<f64>, Vec<String>)> {
+        let policy = policy.parse::<Policy>().unwrap();
+        let results = results();
+        policy.evaluate(&results).map(|breach| {
+            let files = breach
+                .files
+                .iter()
+                .map(|f| path::display(&f.path))
+                .collect();
+            (breach.value, files)
+        })
+    }
+
+    #[test]
+    fn test_parse_policies() {
+        assert_eq!(
+            "lines>1000".parse::<Policy>(),
+            Ok(Policy {
+                language: None,
+                scope: Scope::File,
+                metric: Metric::Lines,
+                comparison: Comparison::Greater,
+                threshold: 1000.0,
+            })
+        );
+        assert_eq!(
+            "language=Visual Basic  count>=2".parse::<Policy>(),
+            Ok(Policy {
+                language: Some("Visual Basic".into()),
+                scope: Scope::Count,
+                metric: Metric::Files,
+                comparison: Comparison::GreaterOrEqual,
+                threshold: 2.0,
+            })
+        );
+        assert_eq!(
+            "total.bytes<=5e5".parse::<Policy>().unwrap().to_string(),
+            "total.bytes<=500000"
+        );
+
+        assert!("lines".parse::<Policy>().is_err());
+        assert!("lines>many".parse::<Policy>().is_err());
+        assert!("nope>1".parse::<Policy>().is_err());
+        assert!("path>1".parse::<Policy>().is_err());
+        assert!("total.max-line-length>1".parse::<Policy>().is_err());
+        assert!("kind=Rust lines>1".parse::<Policy>().is_err());
+    }
+
+    #[test]
+    fn test_evaluate_policies() {
+        assert_eq!(evaluate("lines>10"), Some((None, vec!["big.rs".into()])));
+        assert_eq!(evaluate("lines>100"), None);
+        assert_eq!(evaluate("total.lines>=37"), Some((Some(37.0), vec![])));
+        assert_eq!(evaluate("total.lines>37"), None);
+        assert_eq!(
+            evaluate("language=javascript count>0"),
+            Some((Some(1.0), vec!["app.js".into()]))
+        );
+        assert_eq!(evaluate("language=Go count>0"), None);
+        assert_eq!(
+            evaluate("language=Rust lines<5"),
+            Some((None, vec!["small.rs".into()]))
+        );
+    }
+
+    #[test]
+    fn test_describe_breach() {
+        let results = results();
+        let policy = "language=Rust lines!=0".parse::<Policy>().unwrap();
+        assert_eq!(
+            policy.evaluate(&results).unwrap().to_string(),
+            "Policy failed: language=Rust lines!=0\n  big.rs: 30 lines\n  small.rs: 2 lines\n"
+        );
+        let policy = "count>2".parse::<Policy>().unwrap();
+        assert_eq!(
+            policy.evaluate(&results).unwrap().to_string(),
+            "Policy failed: count>2 (got 3)\n  big.rs\n  small.rs\n  app.js\n"
+        );
+    }
+}
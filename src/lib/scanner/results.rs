@@ -1,27 +1,46 @@
 use serde::Serialize;
 
-use std::collections::HashMap;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+};
 
-use crate::output::Metric;
+use crate::{helpers::path, output::Metric};
 
-use super::{File, Max, Totals};
+use super::{File, Max, Stats, Totals};
 
 // ------------
 // SCAN RESULTS
 // ------------
 
 /// Represents the aggregate scan results
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct ScanResults {
     /// The collection of all file results containing information like the number of lines, words, chars and bytes
     pub files: Vec<File>,
+    /// The aggregate of the files that were [filtered][ScanResults::filter] out of `files`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub others: Option<File>,
     /// The aggregate total number of lines, words, chars and bytes
     pub total: Totals,
     /// The max values for the number of lines, words, chars, and bytes across the results
     pub max: Max,
+    /// The distribution statistics (min, mean, median, percentiles) of the individual files
+    pub stats: Stats,
+    /// The distribution statistics of the individual files in each language (only when grouped by language)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub language_stats: BTreeMap<String, Stats>,
 }
 
 impl ScanResults {
+    /// Adds the file to the results, accumulating its metrics into the totals, the max values and the statistics
+    pub fn push(&mut self, file: File) {
+        self.total.add(&file);
+        self.max.track(&file);
+        self.stats.track(&file);
+        self.files.push(file);
+    }
+
     /// Groups the files by language and returns a new [`ScanResults`] instance
     pub fn group_by_language(&self) -> ScanResults {
         // Group the files by language in a HashMap
@@ -37,42 +56,88 @@ impl ScanResults {
         let mut files = Vec::new();
         let mut total = Totals::default();
         let mut max = Max::default();
+        let mut language_stats = BTreeMap::new();
         for lang in groups.keys() {
-            if let Some(v) = groups.get(lang) {
-                if let Some(file) = v.iter().cloned().reduce(|acc, e| acc + e) {
-                    total.add(&file);
-                    max.track(&file);
-                    files.push(file)
-                }
+            if let Some(v) = groups.get(lang)
+                && let Some(file) = v.iter().cloned().reduce(|acc, e| acc + e)
+            {
+                let stats = language_stats
+                    .entry(lang.to_string())
+                    .or_insert_with(Stats::default);
+                v.iter().for_each(|f| stats.track(f));
+                total.add(&file);
+                max.track(&file);
+                files.push(file)
             }
         }
-        ScanResults { files, total, max }
-    }
-
-    /// Sort the [`ScanResults`] files based on the given column and sort order
-    pub fn sort_by(&mut self, category: Metric, order: &SortOrder) {
-        match category {
-            Metric::Lines => self.files.sort_by(|a, b| match order {
-                SortOrder::Ascending => a.lines.cmp(&b.lines),
-                SortOrder::Descending => b.lines.cmp(&a.lines),
-            }),
-            Metric::Words => self.files.sort_by(|a, b| match order {
-                SortOrder::Ascending => a.words.cmp(&b.words),
-                SortOrder::Descending => b.words.cmp(&a.words),
-            }),
-            Metric::Chars => self.files.sort_by(|a, b| match order {
-                SortOrder::Ascending => a.chars.cmp(&b.chars),
-                SortOrder::Descending => b.chars.cmp(&a.chars),
-            }),
-            Metric::Bytes | _ => self.files.sort_by(|a, b| match order {
-                SortOrder::Ascending => a.bytes.cmp(&b.bytes),
-                SortOrder::Descending => b.bytes.cmp(&a.bytes),
-            }),
+        ScanResults {
+            files,
+            others: self.others.clone(),
+            total,
+            max,
+            stats: self.stats.clone(),
+            language_stats,
+        }
+    }
+
+    /// Keeps the files that satisfy the predicate, up to the first `top` of them (in the current sort order),
+    /// and aggregates all the other files into [`ScanResults::others`]. The totals are left untouched, so they
+    /// still account for every scanned file.
+    pub fn filter<F>(&mut self, top: Option<usize>, predicate: F)
+    where
+        F: Fn(&File) -> bool,
+    {
+        let (mut kept, mut removed): (Vec<_>, Vec<_>) =
+            self.files.drain(..).partition(|file| predicate(file));
+
+        if let Some(top) = top
+            && top < kept.len()
+        {
+            removed.extend(kept.split_off(top));
+        }
+
+        self.files = kept;
+        if !removed.is_empty() {
+            let others = self.others.take().unwrap_or_else(File::others);
+            // The sum takes the path and the language of its right-hand side, i.e. of the aggregate
+            self.others = Some(
+                removed
+                    .into_iter()
+                    .fold(others, |others, file| file + others),
+            );
         }
     }
+
+    /// Sort the [`ScanResults`] files by the given [sort keys][SortKey], in order of priority.
+    ///
+    /// Keys without an explicit order use the given `order`, or their [natural order][SortKey::default_order]
+    /// if there is none. Any remaining ties are broken by the language name and then the path, so that
+    /// the output is deterministic across runs.
+    pub fn sort_by(&mut self, keys: &[SortKey], order: Option<SortOrder>) {
+        let mut files = self.files.drain(..).map(Sortable::from).collect::<Vec<_>>();
+        files.sort_by(|a, b| {
+            keys.iter()
+                .map(|key| key.compare(a, b, order))
+                .chain([
+                    SortKey::from(Metric::Language).compare(a, b, None),
+                    SortKey::from(Metric::Path).compare(a, b, None),
+                ])
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        self.files = files.into_iter().map(|sortable| sortable.file).collect();
+    }
+}
+
+impl FromIterator<File> for ScanResults {
+    fn from_iter<I: IntoIterator<Item = File>>(files: I) -> Self {
+        let mut results = ScanResults::default();
+        files.into_iter().for_each(|file| results.push(file));
+        results
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortOrder {
     Ascending,
     Descending,
@@ -88,3 +153,189 @@ impl std::str::FromStr for SortOrder {
         }
     }
 }
+
+/// A single sort criterion: the [`Metric`] to sort on and (optionally) the [`SortOrder`].
+///
+/// Parsed from specs like `lines`, `lines:desc` or `path:asc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub metric: Metric,
+    pub order: Option<SortOrder>,
+}
+
+impl SortKey {
+    /// The order used when neither the key nor the caller specify one: ascending for
+    /// the textual metrics (language and path) and descending for the numeric ones
+    pub fn default_order(&self) -> SortOrder {
+        match self.metric {
+            Metric::Language | Metric::Path => SortOrder::Ascending,
+            _ => SortOrder::Descending,
+        }
+    }
+
+    /// Compares two files by this key
+    fn compare(&self, a: &Sortable, b: &Sortable, order: Option<SortOrder>) -> Ordering {
+        let ordering = match self.metric {
+            Metric::Language => a.language.cmp(&b.language),
+            Metric::Path => path::natural_cmp(&a.path, &b.path),
+            Metric::Files => a.file.count.cmp(&b.file.count),
+            Metric::Bytes => a.file.bytes.cmp(&b.file.bytes),
+            metric => {
+                let (a, b) = (a.file.value(metric), b.file.value(metric));
+                a.unwrap_or_default().total_cmp(&b.unwrap_or_default())
+            }
+        };
+        match self.order.or(order).unwrap_or(self.default_order()) {
+            SortOrder::Ascending => ordering,
+            SortOrder::Descending => ordering.reverse(),
+        }
+    }
+}
+
+/// A file with its textual sort keys, which are computed once rather than for each comparison
+struct Sortable {
+    /// The lowercase name of the language
+    language: String,
+    /// The displayed path
+    path: String,
+    file: File,
+}
+
+impl From<File> for Sortable {
+    fn from(file: File) -> Self {
+        Sortable {
+            language: file.language.to_string().to_lowercase(),
+            path: path::display(&file.path),
+            file,
+        }
+    }
+}
+
+impl From<Metric> for SortKey {
+    fn from(metric: Metric) -> Self {
+        Self {
+            metric,
+            order: None,
+        }
+    }
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (metric, order) = match s.split_once(':') {
+            Some((metric, order)) => (metric, Some(order.parse()?)),
+            None => (s, None),
+        };
+        Ok(Self {
+            metric: metric.trim().parse()?,
+            order,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::language::Language;
+
+    fn results(lines: &[usize]) -> ScanResults {
+        let mut results = ScanResults::default();
+        for (i, &n) in lines.iter().enumerate() {
+            let contents = "line\n".repeat(n);
+            let mut file = File::scan_reader(std::io::Cursor::new(contents)).unwrap();
+            file.path = format!("{i}.txt").into();
+            results.total.add(&file);
+            results.max.track(&file);
+            results.files.push(file);
+        }
+        results
+    }
+
+    #[test]
+    fn test_filter_top() {
+        let mut results = results(&[1, 5, 3, 4]);
+        results.sort_by(&[Metric::Lines.into()], Some(SortOrder::Descending));
+        results.filter(Some(2), |_| true);
+
+        let lines: Vec<_> = results.files.iter().map(|f| f.lines).collect();
+        assert_eq!(lines, vec![5, 4]);
+
+        let others = results.others.expect("Others should be set");
+        assert_eq!(others.lines, 4);
+        assert_eq!(others.count, 2);
+        assert_eq!(others.path, std::path::Path::new("Others"));
+        assert_eq!(others.language, Language::Unknown(String::new()));
+        assert_eq!(results.total.lines, 13);
+    }
+
+    #[test]
+    fn test_filter_predicate_and_top() {
+        let mut results = results(&[1, 5, 3, 4]);
+        results.sort_by(&[Metric::Lines.into()], Some(SortOrder::Ascending));
+        results.filter(Some(1), |f| f.lines >= 3);
+
+        let lines: Vec<_> = results.files.iter().map(|f| f.lines).collect();
+        assert_eq!(lines, vec![3]);
+        assert_eq!(results.others.map(|f| (f.lines, f.count)), Some((10, 3)));
+    }
+
+    #[test]
+    fn test_filter_nothing_removed() {
+        let mut results = results(&[1, 2]);
+        results.filter(None, |_| true);
+        assert_eq!(results.files.len(), 2);
+        assert!(results.others.is_none());
+    }
+
+    #[test]
+    fn test_parse_sort_key() {
+        assert_eq!("lines".parse(), Ok(SortKey::from(Metric::Lines)));
+        assert_eq!(
+            "path:desc".parse(),
+            Ok(SortKey {
+                metric: Metric::Path,
+                order: Some(SortOrder::Descending)
+            })
+        );
+        assert!("lines:sideways".parse::<SortKey>().is_err());
+        assert!("colour".parse::<SortKey>().is_err());
+    }
+
+    #[test]
+    fn test_sort_by_multiple_keys() {
+        let mut results = results(&[2, 1, 2, 3]);
+        results.files[0].path = "file10.rs".into();
+        results.files[1].path = "file9.rs".into();
+        results.files[2].path = "file2.rs".into();
+        results.files[3].path = "file1.rs".into();
+        results.files[3].language = crate::helpers::language::Language::Rust;
+
+        let keys: Vec<SortKey> = vec!["language".parse().unwrap(), "lines:desc".parse().unwrap()];
+        results.sort_by(&keys, None);
+
+        let paths: Vec<_> = results
+            .files
+            .iter()
+            .map(|f| path::display(&f.path))
+            .collect();
+        assert_eq!(paths, vec!["file1.rs", "file2.rs", "file10.rs", "file9.rs"]);
+    }
+
+    #[test]
+    fn test_sort_by_path_is_natural() {
+        let mut results = results(&[1, 1, 1]);
+        results.files[0].path = "b10.txt".into();
+        results.files[1].path = "b2.txt".into();
+        results.files[2].path = "a.txt".into();
+
+        results.sort_by(&[Metric::Path.into()], None);
+
+        let paths: Vec<_> = results
+            .files
+            .iter()
+            .map(|f| path::display(&f.path))
+            .collect();
+        assert_eq!(paths, vec!["a.txt", "b2.txt", "b10.txt"]);
+    }
+}
@@ -1,23 +1,146 @@
+use std::{
+    io::{self, BufWriter, Write},
+    process::ExitCode,
+};
+
 use clap::Parser;
+use tali::{
+    output,
+    scanner::{Policy, ScanResults, Scanner},
+};
 
 mod cli;
 
+/// The exit code when the results break one of the `--fail-if` policies
+const POLICY_FAILURE: u8 = 3;
+
 /// The main entry-point of the application
-fn main() -> std::io::Result<()> {
+fn main() -> ExitCode {
     let args = cli::Args::parse().process();
-    run(&args)?;
-    Ok(())
+    match run(&args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }
 
 /// Run the main logic of the application by scanning the provided paths and then displaying the results.
-fn run(args: &cli::Args) -> std::io::Result<()> {
-    // Scan the paths for the metrics
-    let results = tali::scanner::Scanner::from(args).scan(&args.paths)?;
+/// Returns the [failure code][POLICY_FAILURE] if the results break any of the policies.
+fn run(args: &cli::Args) -> io::Result<ExitCode> {
+    let scanner = Scanner::from(args);
 
     // Setup the display/output configuration from the command-line arguments
-    let config = tali::output::Config::from(args);
+    let config = output::Config::from(args);
+
+    // Print the formatted output, keeping the footer apart from the data if requested
+    let mut stdout = Output::new(BufWriter::new(io::stdout().lock()));
+    let (breaches, totals) = if args.stream {
+        // Print the rows while the paths are being scanned
+        let files = scanner.files(&args.paths).skip_errors(report);
+        let (results, totals) = output::stream(files, &config, &mut stdout)?;
+        (check(&args.fail_if, &results), totals)
+    } else {
+        // Scan the paths for the metrics
+        let (results, errors) = scanner.scan(&args.paths);
+        errors.into_iter().for_each(report);
+        // Check the policies against the individual files, before they are grouped or filtered for display
+        let breaches = check(&args.fail_if, &results);
+        (
+            breaches,
+            output::write_separately(results, config, &mut stdout)?,
+        )
+    };
+    stdout.finish()?;
+    if let Some(totals) = totals {
+        eprint!("{totals}");
+    }
+
+    // Report the broken policies last, so that they aren't lost above the output
+    if breaches.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    for breach in &breaches {
+        eprint!("{breach}");
+    }
+    Ok(ExitCode::from(POLICY_FAILURE))
+}
+
+/// Reports the errors walking the directories on stderr, skipping the files that can't be read (e.g. binary files)
+/// quietly
+fn report(error: tali::Error) {
+    if let tali::Error::Walk(_) = error {
+        eprintln!("Error: {error}");
+    }
+}
+
+/// Describes the policies the results break
+fn check(policies: &[Policy], results: &ScanResults) -> Vec<String> {
+    policies
+        .iter()
+        .filter_map(|policy| policy.evaluate(results))
+        .map(|breach| breach.to_string())
+        .collect()
+}
+
+/// The standard output, keeping track of whether the output ends with a line break
+///
+/// When the reader stops reading the output (e.g. `tali | head`), which is not an error, the rest of the output is
+/// discarded. The scan still runs to the end, so that the policies are checked against every file.
+struct Output<W: Write> {
+    inner: W,
+    ends_with_newline: bool,
+    closed: bool,
+}
+
+impl<W: Write> Output<W> {
+    fn new(inner: W) -> Self {
+        Output {
+            inner,
+            ends_with_newline: false,
+            closed: false,
+        }
+    }
+
+    /// Ends the output with a line break (if it doesn't already end with one) and flushes it
+    fn finish(mut self) -> io::Result<()> {
+        if !self.ends_with_newline {
+            self.write_all(b"\n")?;
+        }
+        self.flush()
+    }
+
+    /// Discards the output from now on if the reader went away
+    fn unless_closed<T>(&mut self, result: io::Result<T>, discarded: T) -> io::Result<T> {
+        match result {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(discarded)
+            }
+            result => result,
+        }
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.closed {
+            return Ok(buf.len());
+        }
+        let result = self.inner.write(buf);
+        let written = self.unless_closed(result, buf.len())?;
+        if let Some(last) = buf[..written].last() {
+            self.ends_with_newline = *last == b'\n';
+        }
+        Ok(written)
+    }
 
-    // Print the formatted output
-    println!("{}", tali::output::display(results, config));
-    Ok(())
+    fn flush(&mut self) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        let result = self.inner.flush();
+        self.unless_closed(result, ())
+    }
 }
@@ -0,0 +1,79 @@
+use std::{
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+};
+
+fn tali() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_tali"))
+}
+
+#[test]
+fn stops_quietly_when_the_reader_goes_away() {
+    // Enough rows to fill the pipe, so that the writes fail once the reader is gone
+    let mut child = tali()
+        .args(["--stream", "--format", "ndjson"])
+        .args(std::iter::repeat_n("src", 20))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert!(line.starts_with('{'));
+    drop(stdout);
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[test]
+fn checks_the_policies_when_the_reader_goes_away() {
+    for stream in [false, true] {
+        let mut child = tali()
+            .args(["--format", "ndjson", "--fail-if", "count>0"])
+            .args(stream.then_some("--stream"))
+            .arg("src")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("Policy failed: count>0 "));
+    }
+}
+
+#[test]
+fn fails_with_the_policy_exit_code() {
+    let output = tali()
+        .args(["Cargo.toml", "--format", "plain", "--fail-if", "lines>1"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .starts_with("Policy failed: lines>1\n  Cargo.toml: ")
+    );
+
+    let output = tali()
+        .args(["Cargo.toml", "--fail-if", "lines>100000"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn ends_the_output_with_a_line_break() {
+    let output = tali()
+        .args(["Cargo.toml", "--format", "json"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.ends_with("}\n"));
+    assert!(!stdout.ends_with("\n\n"));
+}
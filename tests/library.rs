@@ -0,0 +1,175 @@
+use std::{cell::RefCell, io::Write, path::Path, rc::Rc};
+
+use tali::{
+    Error, Language, Table,
+    output::{self, Column, Config, Format, Formatter, JSONFormatter, Metric},
+    scanner::{ScanResults, Scanner},
+};
+
+/// A directory with a few files in different languages
+fn fixture() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let files = [
+        ("src/main.rs", "fn main() {\n    println!(\"hello\");\n}\n"),
+        ("src/lib.rs", "pub mod a;\n"),
+        ("README.md", "# Title\n\nSome words here\n"),
+    ];
+    for (path, contents) in files {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+fn names(results: &ScanResults) -> Vec<String> {
+    let mut names = results
+        .files
+        .iter()
+        .map(|file| file.path.file_name().unwrap().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+fn scan_collects_the_files_and_totals() {
+    let dir = fixture();
+    let (results, _) = Scanner::new().scan(&[dir.path()]);
+
+    assert_eq!(names(&results), ["README.md", "lib.rs", "main.rs"]);
+    assert_eq!(results.total.files, 3);
+    assert_eq!(results.total.lines, 7);
+    assert_eq!(results.max.lines, 3);
+}
+
+#[test]
+fn files_yields_each_file_as_it_is_scanned() {
+    let dir = fixture();
+    let scanner = Scanner::new();
+    let mut files = scanner.files(&[dir.path().join("src")]);
+
+    let first = files.next().unwrap().unwrap();
+    assert_eq!(first.language, Language::Rust);
+    assert_eq!(files.count(), 1);
+
+    // The iterator collects into the same results as a scan
+    let results = scanner
+        .files(&[dir.path()])
+        .collect::<Result<ScanResults, _>>()
+        .unwrap();
+    let (scanned, _) = scanner.scan(&[dir.path()]);
+    assert_eq!(names(&results), names(&scanned));
+    assert_eq!(results.total.bytes, scanned.total.bytes);
+}
+
+#[test]
+fn files_reports_missing_paths() {
+    let dir = fixture();
+    let missing = dir.path().join("missing.rs");
+    let files = Scanner::new()
+        .files(&[missing.as_path(), dir.path().join("README.md").as_path()])
+        .collect::<Vec<_>>();
+
+    assert_eq!(files.len(), 2);
+    match &files[0] {
+        Err(Error::Io { path, source }) => {
+            assert_eq!(path, &missing);
+            assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+        }
+        other => panic!("expected a missing file, got {other:?}"),
+    }
+    assert!(files[1].is_ok());
+
+    // The scan skips the paths it can't read, and returns their errors
+    let (results, errors) = Scanner::new().scan(&[missing.as_path()]);
+    assert!(results.files.is_empty());
+    assert!(matches!(&errors[..], [Error::Io { path, .. }] if path == &missing));
+}
+
+#[test]
+fn write_renders_into_any_writer() {
+    let dir = fixture();
+    let (results, _) = Scanner::new().scan(&[dir.path()]);
+    let config = Config {
+        columns: vec![Column::Language, Column::Lines],
+        group_by_language: true,
+        format: Format::CSV,
+        ..Default::default()
+    };
+
+    let mut csv = Vec::new();
+    output::write(results, config, &mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "Language,Lines\nRust,4\nMarkdown,3\nTotal,7\n"
+    );
+}
+
+#[test]
+fn formatters_can_be_used_directly() {
+    let dir = fixture();
+    let (results, _) = Scanner::new().scan(&[Path::new(dir.path()).join("README.md")]);
+    let config = Config::default();
+
+    let mut json = Vec::new();
+    JSONFormatter::default()
+        .write(&results, &config, &mut json)
+        .unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(value["total"]["lines"], 3);
+    assert_eq!(value["files"][0]["language"], "Markdown");
+}
+
+/// A writer whose contents can be inspected while it is being written to
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn stream_writes_each_row_as_the_file_is_scanned() {
+    let dir = fixture();
+    let scanner = Scanner::new();
+    let config = Config {
+        columns: vec![Column::Path, Column::Lines, Column::Percent(Metric::Lines)],
+        format: Format::CSV,
+        ..Default::default()
+    };
+
+    let output = Shared::default();
+    let mut scanned = 0;
+    let files = scanner.files(&[dir.path()]).skip_errors(drop).inspect(|_| {
+        // The header and the rows of the files before this one are already written
+        let lines = output.0.borrow().iter().filter(|b| **b == b'\n').count();
+        assert_eq!(lines, 1 + scanned);
+        scanned += 1;
+    });
+    let (results, totals) = output::stream(files, &config, &mut output.clone()).unwrap();
+
+    assert_eq!(results.files.len(), 3);
+    assert_eq!(totals, None);
+    let output = String::from_utf8(output.0.take()).unwrap();
+    assert!(output.starts_with("Path,Lines\n"));
+    assert!(output.ends_with("\n3,7\n"));
+}
+
+#[test]
+fn table_is_public() {
+    let mut table = Table::from_csv("Language,Files\nRust,12\nMarkdown,3");
+    let output = table.display();
+    assert!(
+        output
+            .lines()
+            .any(|line| line.contains("Rust") && line.contains("12"))
+    );
+    assert_eq!(Language::from_path("main.rs"), Language::Rust);
+}